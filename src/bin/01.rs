@@ -1,29 +1,11 @@
-use std::io::stdin;
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    io::stdin,
+};
 
 use phf::phf_map;
 
-fn digits(l: &str) -> Vec<u32> {
-    let mut r = Vec::new();
-    for c in l.chars() {
-        match c.to_digit(10) {
-            Some(d) => r.push(d),
-            None => {}
-        }
-    }
-
-    return r;
-}
-
-fn part_one() {
-    let mut total = 0u32;
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        let digits = digits(&line);
-        total += digits[0] * 10 + digits[digits.len() - 1]
-    }
-
-    println!("{total}");
-}
-
 const DIGITS_MAP: phf::Map<&str, u32> = phf_map! {
     "one" => 1,
     "two" => 2,
@@ -36,63 +18,141 @@ const DIGITS_MAP: phf::Map<&str, u32> = phf_map! {
     "nine" => 9,
 };
 
-fn first_and_last_digit(l: &str) -> Vec<u32> {
-    let mut first = 0;
-    let mut last = 0;
-
-    let mut so_far = String::new();
-    'out: for b in l.bytes() {
-        let c = b as char;
-        match c.to_digit(10) {
-            Some(d) => {
-                first = d;
-                break;
-            }
-            None => {
-                so_far.push(c);
-                for (&k, &v) in DIGITS_MAP.entries() {
-                    if so_far.ends_with(k) {
-                        first = v;
-                        break 'out;
+// Aho-Corasick automaton: a trie of all the words where every node also knows the longest suffix of it that's
+// still in the trie, so a line can be scanned once and every match (overlapping ones too) comes out along the way
+struct Matcher {
+    next: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    // Words ending at every node, as (length in chars, value)
+    output: Vec<Vec<(usize, u32)>>,
+}
+
+impl Matcher {
+    fn new(words: &[(String, u32)]) -> Matcher {
+        let mut matcher = Matcher {
+            next: vec![HashMap::new()],
+            fail: vec![0],
+            output: vec![Vec::new()],
+        };
+
+        for (word, value) in words {
+            let mut node = 0;
+            for c in word.chars() {
+                node = match matcher.next[node].get(&c) {
+                    Some(&n) => n,
+                    None => {
+                        matcher.next.push(HashMap::new());
+                        matcher.fail.push(0);
+                        matcher.output.push(Vec::new());
+                        let n = matcher.next.len() - 1;
+                        matcher.next[node].insert(c, n);
+                        n
                     }
+                };
+            }
+            matcher.output[node].push((word.chars().count(), *value));
+        }
+
+        // Breadth first, so the fail link of a node's parent is always done before the node itself
+        let mut queue = matcher.next[0].values().copied().collect::<VecDeque<_>>();
+        while let Some(node) = queue.pop_front() {
+            let children = matcher.next[node].iter().map(|(&c, &n)| (c, n)).collect::<Vec<_>>();
+            for (c, child) in children {
+                let mut f = matcher.fail[node];
+                while f != 0 && !matcher.next[f].contains_key(&c) {
+                    f = matcher.fail[f];
                 }
+                let fail = matcher.next[f].get(&c).copied().unwrap_or(0);
+
+                matcher.fail[child] = fail;
+                let inherited = matcher.output[fail].clone();
+                matcher.output[child].extend(inherited);
+                queue.push_back(child);
             }
         }
+
+        matcher
     }
 
-    so_far = String::new();
-    'out: for b in l.bytes().rev() {
-        let c = b as char;
-        match c.to_digit(10) {
-            Some(d) => {
-                last = d;
-                break;
+    // Every match in the line as (start in chars, value), ordered by where they end
+    fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = (usize, u32)> + 'a {
+        let mut state = 0;
+        line.chars().enumerate().flat_map(move |(i, c)| {
+            while state != 0 && !self.next[state].contains_key(&c) {
+                state = self.fail[state];
             }
-            None => {
-                so_far.insert(0, c);
-                for (&k, &v) in DIGITS_MAP.entries() {
-                    if so_far.starts_with(k) {
-                        last = v;
-                        break 'out;
-                    }
-                }
+            state = self.next[state].get(&c).copied().unwrap_or(0);
+
+            self.output[state].iter().map(move |&(len, value)| (i + 1 - len, value))
+        })
+    }
+
+    // Values of the matches that start first and last. If two words start at the same place the longer one wins -
+    // it ends later so it's found after the shorter one
+    fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let mut first: Option<(usize, u32)> = None;
+        let mut last: Option<(usize, u32)> = None;
+
+        for (start, value) in self.matches(line) {
+            if first.is_none_or(|(s, _)| start <= s) {
+                first = Some((start, value));
+            }
+            if last.is_none_or(|(s, _)| start >= s) {
+                last = Some((start, value));
             }
         }
+
+        Some((first?.1, last?.1))
     }
+}
 
-    return vec![first, last];
+fn digit_words() -> Vec<(String, u32)> {
+    (0..10).map(|d| (d.to_string(), d)).collect()
 }
 
-fn part_two() {
-    let mut total = 0u32;
+fn calibration_total(matcher: &Matcher) -> u64 {
+    let mut total = 0u64;
     for line in stdin().lines().map(|l| l.unwrap()) {
-        let digits = first_and_last_digit(&line);
-        total += digits[0] * 10 + digits[digits.len() - 1]
+        // Lines without any digits don't add anything
+        if let Some((first, last)) = matcher.first_and_last(&line) {
+            total += first as u64 * 10 + last as u64;
+        }
     }
 
+    total
+}
+
+fn part_one() {
+    let total = calibration_total(&Matcher::new(&digit_words()));
+    println!("{total}");
+}
+
+fn part_two(words: &[(String, u32)]) {
+    let mut vocabulary = digit_words();
+    vocabulary.extend(words.iter().cloned());
+
+    let total = calibration_total(&Matcher::new(&vocabulary));
     println!("{total}");
 }
 
 fn main() {
-    part_two();
+    // `--words "eins=1,zwei=2,..."` spells the digits out with those words instead of the English ones
+    let args = env::args().collect::<Vec<_>>();
+    let words = match args.iter().position(|arg| arg == "--words") {
+        Some(i) => args
+            .get(i + 1)
+            .expect("--words needs a list of words")
+            .split(',')
+            .map(|entry| {
+                let (word, value) = entry.split_once('=').expect("words look like word=value");
+                (word.to_string(), value.parse().expect("values are numbers"))
+            })
+            .collect(),
+        None => DIGITS_MAP
+            .entries()
+            .map(|(&word, &value)| (word.to_string(), value))
+            .collect::<Vec<_>>(),
+    };
+
+    part_two(&words);
 }
@@ -1,13 +1,17 @@
 use std::cmp::max;
-use std::io::stdin;
+use std::collections::BTreeMap;
+use std::{env, io::stdin};
 
 use scanf::sscanf;
 
-#[derive(Debug, Clone, Copy)]
-struct CubeSet {
-    red: i32,
-    green: i32,
-    blue: i32,
+// How many cubes of each colour. Colours that aren't in there count as 0
+#[derive(Debug, Clone, Default)]
+struct CubeSet(BTreeMap<String, i32>);
+
+impl CubeSet {
+    fn get(&self, colour: &str) -> i32 {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
 }
 
 #[derive(Clone)]
@@ -16,22 +20,22 @@ struct Game {
     sets: Vec<CubeSet>,
 }
 
+// A draw that needed more cubes of some colour than the bag has
+#[derive(Debug)]
+struct Violation {
+    draw: usize,
+    colour: String,
+    drawn: i32,
+    available: i32,
+}
+
 fn parse_set(s: &str) -> CubeSet {
-    let mut result: CubeSet = CubeSet {
-        red: 0,
-        green: 0,
-        blue: 0,
-    };
+    let mut result = CubeSet::default();
     for part in s.split(", ") {
         let split = part.split_ascii_whitespace().collect::<Vec<_>>();
         let amount = split[0].parse::<i32>().expect("expected an int for amount");
-        let color = split[1];
-        match color {
-            "red" => result.red = amount,
-            "green" => result.green = amount,
-            "blue" => result.blue = amount,
-            _ => todo!("unexpected color {color}"),
-        }
+        let colour = split.get(1).unwrap_or_else(|| panic!("{part} has no colour"));
+        result.0.insert(colour.to_string(), amount);
     }
 
     return result;
@@ -51,19 +55,39 @@ fn parse_input() -> Vec<Game> {
     return result;
 }
 
-fn possible(g: &Game, available: CubeSet) -> bool {
-    return g.sets.iter().all(|&set| {
-        set.red <= available.red && set.green <= available.green && set.blue <= available.blue
-    });
+fn violations(g: &Game, available: &CubeSet) -> Vec<Violation> {
+    let mut result = Vec::new();
+    for (draw, set) in g.sets.iter().enumerate() {
+        for (colour, &drawn) in &set.0 {
+            if drawn > available.get(colour) {
+                result.push(Violation {
+                    draw: draw + 1,
+                    colour: colour.clone(),
+                    drawn,
+                    available: available.get(colour),
+                });
+            }
+        }
+    }
+
+    result
 }
 
-fn part_one() {
+fn possible(g: &Game, available: &CubeSet) -> bool {
+    return violations(g, available).is_empty();
+}
+
+fn part_one(available: &CubeSet) {
     let input = parse_input();
-    let available = CubeSet {
-        red: 12,
-        green: 13,
-        blue: 14,
-    };
+
+    for game in &input {
+        for v in violations(game, available) {
+            println!(
+                "Game {} is impossible: draw {} has {} {}, but the bag only has {}",
+                game.id, v.draw, v.drawn, v.colour, v.available
+            );
+        }
+    }
 
     let result = input
         .iter()
@@ -74,36 +98,50 @@ fn part_one() {
     println!("{result}")
 }
 
-fn power(cubeset: CubeSet) -> i32 {
-    return cubeset.red * cubeset.green * cubeset.blue;
+// Product over all the given colours, so a colour the bag doesn't need makes the power 0
+fn power(cubeset: &CubeSet, colours: &[String]) -> i32 {
+    return colours.iter().map(|colour| cubeset.get(colour)).product();
 }
 
 fn min_required(game: &Game) -> CubeSet {
-    let (mut red, mut green, mut blue) = (0, 0, 0);
+    let mut result = CubeSet::default();
     for set in &game.sets {
-        red = max(set.red, red);
-        green = max(set.green, green);
-        blue = max(set.blue, blue);
+        for (colour, &amount) in &set.0 {
+            let needed = result.0.entry(colour.clone()).or_insert(0);
+            *needed = max(*needed, amount);
+        }
     }
 
-    return CubeSet {
-        red: red,
-        green: green,
-        blue: blue,
-    };
+    return result;
 }
 
 fn part_two() {
     let input = parse_input();
 
+    // Every colour that shows up anywhere
+    let mut colours = input
+        .iter()
+        .flat_map(|game| game.sets.iter().flat_map(|set| set.0.keys().cloned()))
+        .collect::<Vec<_>>();
+    colours.sort();
+    colours.dedup();
+
     let result = input
         .iter()
-        .map(|game| power(min_required(game)))
+        .map(|game| power(&min_required(game), &colours))
         .sum::<i32>();
 
     println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--bag "12 red, 13 green, 14 blue"` checks which games are possible with that bag (explaining the ones that
+    // aren't) instead
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--bag") {
+        let bag = parse_set(args.get(i + 1).expect("--bag needs the bag's contents"));
+        part_one(&bag);
+    } else {
+        part_two();
+    }
 }
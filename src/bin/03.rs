@@ -1,7 +1,4 @@
-use std::{
-    collections::{HashMap, HashSet},
-    io::stdin,
-};
+use std::{env, io::stdin};
 
 fn neighbours(pos: (usize, usize), limits: (usize, usize)) -> Vec<(usize, usize)> {
     let mut result = Vec::new();
@@ -19,95 +16,225 @@ fn neighbours(pos: (usize, usize), limits: (usize, usize)) -> Vec<(usize, usize)
     result
 }
 
-fn char(s: &str, i: usize) -> char {
-    s.as_bytes()[i] as char
+#[derive(Debug)]
+struct Number {
+    value: i64,
+    row: usize,
+    // Columns `start..end`
+    start: usize,
+    end: usize,
 }
 
-fn part_one() {
-    let mut result = 0;
-    let lines = stdin().lines().map(|l| l.unwrap()).collect::<Vec<_>>();
-    for (i, line) in lines.iter().enumerate() {
-        let mut j = 0;
-        let n = line.chars().count();
-        while j < n {
-            if char(line, j).is_digit(10) {
-                let mut have_symbol = false;
-                let mut val = 0;
-
-                while j < n {
-                    let c = char(line, j);
-                    if !c.is_digit(10) {
-                        break;
-                    }
+#[derive(Debug)]
+struct Symbol {
+    c: char,
+    row: usize,
+    col: usize,
+}
+
+// The grid parsed once, with every number and symbol knowing which ones of the other kind are next to it (as indices
+// into `numbers` and `symbols`)
+struct Schematic {
+    lines: Vec<Vec<char>>,
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    symbol_numbers: Vec<Vec<usize>>,
+    number_symbols: Vec<Vec<usize>>,
+}
 
-                    have_symbol |= neighbours((i, j), (lines.len(), n))
-                        .iter()
-                        .any(|&(i1, j1)| {
-                            let c = lines[i1 as usize].chars().nth(j1 as usize).unwrap();
-                            return !c.is_digit(10) && c != '.';
-                        });
-                    val = val * 10 + (c as i32) - ('0' as i32);
-                    j += 1;
+impl Schematic {
+    fn parse(lines: &[String]) -> Schematic {
+        let lines = lines.iter().map(|l| l.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+        let limits = (lines.len(), lines.first().map_or(0, |l| l.len()));
+
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        // Which number covers every cell
+        let mut number_at = vec![vec![None; limits.1]; limits.0];
+
+        for (i, line) in lines.iter().enumerate() {
+            let mut j = 0;
+            while j < line.len() {
+                let c = line[j];
+                if c.is_ascii_digit() {
+                    let start = j;
+                    let mut value = 0;
+                    while j < line.len() && line[j].is_ascii_digit() {
+                        value = value * 10 + line[j].to_digit(10).unwrap() as i64;
+                        number_at[i][j] = Some(numbers.len());
+                        j += 1;
+                    }
+                    numbers.push(Number {
+                        value,
+                        row: i,
+                        start,
+                        end: j,
+                    });
+                    continue;
                 }
 
-                if have_symbol {
-                    result += val;
+                if c != '.' {
+                    symbols.push(Symbol { c, row: i, col: j });
                 }
+                j += 1;
+            }
+        }
+
+        let mut symbol_numbers = Vec::new();
+        let mut number_symbols = vec![Vec::new(); numbers.len()];
+        for (s, symbol) in symbols.iter().enumerate() {
+            let mut adjacent = neighbours((symbol.row, symbol.col), limits)
+                .iter()
+                .filter_map(|&(i, j)| number_at[i][j])
+                .collect::<Vec<_>>();
+            adjacent.sort();
+            adjacent.dedup();
+
+            for &n in &adjacent {
+                number_symbols[n].push(s);
             }
-            j += 1;
+            symbol_numbers.push(adjacent);
+        }
+
+        Schematic {
+            lines,
+            numbers,
+            symbols,
+            symbol_numbers,
+            number_symbols,
         }
     }
-    println!("{result}");
-}
 
-fn number_including(i: usize, j: usize, lines: &Vec<String>) -> (i32, (usize, usize)) {
-    let (mut start, mut end) = (j as i32, j);
-    let line = &lines[i];
+    fn read() -> Schematic {
+        let lines = stdin().lines().map(|l| l.unwrap()).collect::<Vec<_>>();
+        Schematic::parse(&lines)
+    }
 
-    while start >= 0 && char(line, start as usize).is_ascii_digit() {
-        start -= 1;
+    fn adjacent_numbers(&self, symbol: usize) -> Vec<&Number> {
+        self.symbol_numbers[symbol].iter().map(|&n| &self.numbers[n]).collect()
     }
 
-    while end < line.len() && char(line, end).is_ascii_digit() {
-        end += 1;
+    // Every number next to some symbol `c`, each one only once even if it touches several of them
+    fn numbers_next_to(&self, c: char) -> Vec<&Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| symbols.iter().any(|&s| self.symbols[s].c == c))
+            .map(|(number, _)| number)
+            .collect()
     }
 
-    let start = (start + 1) as usize;
-    return (line[start..end].parse::<i32>().unwrap(), (i, start));
-}
+    // `c` symbols with exactly `k` numbers around them, along with those numbers
+    fn gears(&self, c: char, k: usize) -> Vec<(&Symbol, Vec<&Number>)> {
+        (0..self.symbols.len())
+            .filter(|&s| self.symbols[s].c == c && self.symbol_numbers[s].len() == k)
+            .map(|s| (&self.symbols[s], self.adjacent_numbers(s)))
+            .collect()
+    }
+
+    fn lonely_symbols(&self) -> Vec<&Symbol> {
+        (0..self.symbols.len())
+            .filter(|&s| self.symbol_numbers[s].is_empty())
+            .map(|s| &self.symbols[s])
+            .collect()
+    }
 
-fn adjacent_numbers(i: usize, j: usize, lines: &Vec<String>) -> Vec<i32> {
-    let mut number_pos: HashMap<(usize, usize), i32> = HashMap::new();
-    let limits = (lines.len(), lines[0].len());
+    // Part numbers in green and the other numbers dimmed, gears (`*` next to exactly two numbers) in bold magenta,
+    // symbols without any numbers in red and the rest of them in yellow
+    fn render(&self) -> String {
+        let mut styles = self
+            .lines
+            .iter()
+            .map(|line| vec![""; line.len()])
+            .collect::<Vec<_>>();
+
+        for (number, symbols) in self.numbers.iter().zip(&self.number_symbols) {
+            let style = if symbols.is_empty() { "\x1b[2m" } else { "\x1b[92m" };
+            styles[number.row][number.start..number.end].fill(style);
+        }
+        for (symbol, numbers) in self.symbols.iter().zip(&self.symbol_numbers) {
+            styles[symbol.row][symbol.col] = match (symbol.c, numbers.len()) {
+                (_, 0) => "\x1b[91m",
+                ('*', 2) => "\x1b[1;95m",
+                _ => "\x1b[93m",
+            };
+        }
 
-    for cell in neighbours((i, j), limits) {
-        let c = char(&lines[cell.0 as usize], cell.1 as usize);
-        if c.is_ascii_digit() {
-            let (val, pos) = number_including(cell.0, cell.1, lines);
-            number_pos.insert(pos, val);
+        let mut result = String::new();
+        for (line, styles) in self.lines.iter().zip(&styles) {
+            for (c, style) in line.iter().zip(styles) {
+                result += &format!("{style}{c}\x1b[0m");
+            }
+            result += "\n";
         }
+
+        result
     }
+}
 
-    number_pos.values().map(|&v| v).collect::<Vec<_>>()
+fn part_one() {
+    let schematic = Schematic::read();
+    // Part numbers are the ones next to at least one symbol
+    let result = schematic
+        .numbers
+        .iter()
+        .zip(&schematic.number_symbols)
+        .filter(|(_, symbols)| !symbols.is_empty())
+        .map(|(number, _)| number.value)
+        .sum::<i64>();
+
+    println!("{result}");
 }
 
-fn part_two() {
-    let mut result = 0i64;
-    let lines = stdin().lines().map(|l| l.unwrap()).collect::<Vec<_>>();
+// Sum of the products of the numbers around every `*` that has exactly `k` of them
+fn part_two(k: usize) {
+    let schematic = Schematic::read();
+    let result = schematic
+        .gears('*', k)
+        .iter()
+        .map(|(_, numbers)| numbers.iter().map(|n| n.value).product::<i64>())
+        .sum::<i64>();
 
-    for (i, line) in lines.iter().enumerate() {
-        for (j, c) in line.chars().enumerate() {
-            if c == '*' {
-                let numbers = adjacent_numbers(i, j, &lines);
-                if numbers.len() == 2 {
-                    result += (numbers[0] * numbers[1]) as i64;
-                }
-            }
+    println!("{result}");
+}
+
+fn render() {
+    let schematic = Schematic::read();
+    print!("{}", schematic.render());
+
+    let lonely = schematic.lonely_symbols();
+    if !lonely.is_empty() {
+        println!("Symbols without numbers:");
+        for symbol in lonely {
+            println!("  {} at ({}, {})", symbol.c, symbol.row, symbol.col);
+        }
+    }
+    let mut kinds = schematic.symbols.iter().map(|s| s.c).collect::<Vec<_>>();
+    kinds.sort();
+    kinds.dedup();
+    for c in kinds {
+        let numbers = schematic.numbers_next_to(c);
+        if !numbers.is_empty() {
+            let values = numbers.iter().map(|n| n.value.to_string()).collect::<Vec<_>>();
+            println!("Next to {c}: {}", values.join(", "));
         }
     }
-    println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--render` draws the schematic with the part numbers and gears highlighted, `--gears <k>` looks for gears with
+    // k numbers around them instead of two
+    let args = env::args().collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--render") {
+        render();
+    } else {
+        let k = match args.iter().position(|arg| arg == "--gears") {
+            Some(i) => args
+                .get(i + 1)
+                .and_then(|k| k.parse().ok())
+                .expect("--gears needs a number"),
+            None => 2,
+        };
+        part_two(k);
+    }
 }
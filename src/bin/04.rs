@@ -1,11 +1,31 @@
-use std::{collections::HashSet, io::stdin};
+use std::{env, io::stdin};
 
+use rug::Integer;
 use scanf::sscanf;
 
+// A set of non-negative numbers, one bit each
+#[derive(Default)]
+struct NumberSet {
+    words: Vec<u64>,
+}
+
+impl NumberSet {
+    fn insert(&mut self, n: usize) {
+        if n / 64 >= self.words.len() {
+            self.words.resize(n / 64 + 1, 0);
+        }
+        self.words[n / 64] |= 1 << (n % 64);
+    }
+
+    fn contains(&self, n: usize) -> bool {
+        self.words.get(n / 64).is_some_and(|word| word >> (n % 64) & 1 == 1)
+    }
+}
+
 struct Card {
     number: i32,
-    winning: Vec<i32>,
-    have: Vec<i32>,
+    winning: NumberSet,
+    have: Vec<usize>,
 }
 
 fn read_input() -> Vec<Card> {
@@ -14,13 +34,13 @@ fn read_input() -> Vec<Card> {
         let mut id: i32 = 0;
         let (mut winning_str, mut have_str) = (String::new(), String::new());
         if sscanf!(&line, "Card {i32}: {} | {}", id, winning_str, have_str).is_ok() {
-            let winning = winning_str
-                .split_ascii_whitespace()
-                .map(|n| n.parse::<i32>().unwrap())
-                .collect::<Vec<_>>();
+            let mut winning = NumberSet::default();
+            for n in winning_str.split_ascii_whitespace() {
+                winning.insert(n.parse::<usize>().unwrap());
+            }
             let have = have_str
                 .split_ascii_whitespace()
-                .map(|n| n.parse::<i32>().unwrap())
+                .map(|n| n.parse::<usize>().unwrap())
                 .collect::<Vec<_>>();
             result.push(Card {
                 number: id,
@@ -34,40 +54,93 @@ fn read_input() -> Vec<Card> {
 }
 
 fn count_winning(c: &Card) -> usize {
-    return c.have.iter().filter(|&n| c.winning.contains(n)).count();
+    return c.have.iter().filter(|&&n| c.winning.contains(n)).count();
 }
 
-fn card_worth(c: &Card) -> i32 {
+fn card_worth(c: &Card) -> Integer {
     let common_cnt = count_winning(c);
 
     if common_cnt == 0 {
-        return 0;
+        return Integer::new();
     }
-    return 1 << (common_cnt - 1);
+    return Integer::from(1) << (common_cnt - 1) as u32;
 }
 
 fn part_one() {
     let cards = read_input();
-    let result = cards.iter().map(|c| card_worth(c)).sum::<i32>();
+    let result = cards.iter().map(card_worth).sum::<Integer>();
     println!("{result}");
 }
 
-fn part_two() {
-    let cards = read_input();
+// How many copies of every card you end up with. The counts grow exponentially along chains of winning cards, so
+// they can get arbitrarily large
+struct Cascade {
+    matches: Vec<usize>,
+    copies: Vec<Integer>,
+}
 
-    let mut counts = vec![1; cards.len()];
-    for i in 0..cards.len() {
-        let cnt = count_winning(&cards[i]);
+impl Cascade {
+    fn new(cards: &[Card]) -> Cascade {
+        let matches = cards.iter().map(count_winning).collect::<Vec<_>>();
 
-        for j in i + 1..=std::cmp::min(i + cnt, cards.len() - 1) {
-            counts[j] += counts[i];
+        let mut copies = vec![Integer::from(1); cards.len()];
+        for i in 0..cards.len() {
+            let won = copies[i].clone();
+            let last = std::cmp::min(i + matches[i], cards.len() - 1);
+            for copy in &mut copies[i + 1..=last] {
+                *copy += &won;
+            }
         }
+
+        Cascade { matches, copies }
     }
 
-    let result = counts.iter().sum::<i32>();
+    fn total(&self) -> Integer {
+        self.copies.iter().sum()
+    }
+
+    // Where the copies of the `card`-th card came from: every copy of an earlier card that reaches this far wins one
+    // of them. Together with the original they add up to `copies[card]`
+    fn provenance(&self, card: usize) -> Vec<(usize, &Integer)> {
+        (0..card)
+            .filter(|&i| i + self.matches[i] >= card)
+            .map(|i| (i, &self.copies[i]))
+            .collect()
+    }
+}
+
+fn part_two() {
+    let cards = read_input();
+
+    let result = Cascade::new(&cards).total();
     println!("{result}");
 }
 
+fn explain(number: i32) {
+    let cards = read_input();
+    let cascade = Cascade::new(&cards);
+
+    let card = cards
+        .iter()
+        .position(|c| c.number == number)
+        .unwrap_or_else(|| panic!("there's no card {number}"));
+    println!("Card {number}: {} copies", cascade.copies[card]);
+    println!("  1 original");
+    for (i, copies) in cascade.provenance(card) {
+        println!("  {copies} from card {}", cards[i].number);
+    }
+}
+
 fn main() {
-    part_two();
+    // `--explain <card>` shows how many of that card's copies were won by each earlier card
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--explain") {
+        let number = args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .expect("--explain needs a card number");
+        explain(number);
+    } else {
+        part_two();
+    }
 }
@@ -1,11 +1,13 @@
-use std::{cmp, io::stdin};
+use std::{env, io::stdin, slice};
 
+use rug::Integer;
 use scanf::sscanf;
 
-#[derive(Clone, Copy, Debug)]
+// Arbitrarily large, since part two glues all the digits together
+#[derive(Clone, Debug)]
 struct Race {
-    time: i64,
-    distance: i64,
+    time: Integer,
+    distance: Integer,
 }
 
 fn read_input() -> Vec<Race> {
@@ -17,62 +19,79 @@ fn read_input() -> Vec<Race> {
         if sscanf!(&line, "Time: {}", times_str).is_ok() {
             times = times_str
                 .split_ascii_whitespace()
-                .map(|part| part.parse::<i64>().unwrap())
+                .map(|part| part.parse::<Integer>().unwrap())
                 .collect::<Vec<_>>();
         } else if sscanf!(&line, "Distance: {}", distance_str).is_ok() {
             distances = distance_str
                 .split_ascii_whitespace()
-                .map(|part| part.parse::<i64>().unwrap())
+                .map(|part| part.parse::<Integer>().unwrap())
                 .collect::<Vec<_>>();
         }
     }
 
     let mut races = Vec::new();
-    for i in 0..times.len() {
-        races.push(Race {
-            time: times[i],
-            distance: distances[i],
-        });
+    for (time, distance) in times.into_iter().zip(distances) {
+        races.push(Race { time, distance });
     }
 
     races
 }
 
-fn ways_to_win(r: Race) -> i64 {
-    let (d, t) = (r.distance, r.time);
-    let discr = (t * t - 4 * d) as f64;
-    let (x1, x2) = (
-        (((t as f64) - discr.sqrt()) / 2.0),
-        (((t as f64) + discr.sqrt()) / 2.0),
-    );
-
-    let x1 = if x1 == x1.ceil() {
-        (x1.ceil() as i64) + 1
-    } else {
-        x1.ceil() as i64
-    };
-
-    let x2 = if x2 == x2.floor() {
-        (x2.floor() as i64) - 1
-    } else {
-        x2.floor() as i64
-    };
-
-    let (t1, t2) = (cmp::max(x1, 0), cmp::min(x2, r.time));
-
-    return t2 - t1 + 1;
+fn wins(hold: &Integer, r: &Race) -> bool {
+    Integer::from(&r.time - hold) * hold > r.distance
 }
 
-fn part_one() {
+// Holding for h goes h * (t - h), which beats d between the roots of h^2 - t * h + d, (t +- sqrt(t^2 - 4 * d)) / 2.
+// With the integer square root the smallest winning h is within a step of (t - isqrt) / 2, so only a few holds
+// around it need checking. If none of them win, the best distance is at most the record. The rest follows from h
+// and t - h going the same distance
+fn winning_holds(r: &Race) -> Option<(Integer, Integer)> {
+    let discr = Integer::from(&r.time * &r.time) - Integer::from(&r.distance * 4);
+    if discr < 0 {
+        return None;
+    }
+
+    let estimate: Integer = (&r.time - discr.sqrt()) / 2;
+    let low = (-1..=2)
+        .map(|offset| Integer::from(&estimate + offset))
+        .find(|hold| *hold >= 0 && wins(hold, r))?;
+
+    let high = Integer::from(&r.time - &low);
+    Some((low, high))
+}
+
+fn ways_to_win(r: &Race) -> Integer {
+    match winning_holds(r) {
+        Some((low, high)) => high - low + 1,
+        None => Integer::ZERO,
+    }
+}
+
+fn print_intervals(races: &[Race]) {
+    for r in races {
+        match winning_holds(r) {
+            Some((low, high)) => eprintln!(
+                "Race of {} ms, record {} mm: hold for {low}..={high} ms",
+                r.time, r.distance
+            ),
+            None => eprintln!("Race of {} ms, record {} mm: can't be won", r.time, r.distance),
+        }
+    }
+}
+
+fn part_one(intervals: bool) {
     let races = read_input();
-    let result = races.iter().map(|r| ways_to_win(*r)).product::<i64>();
+    if intervals {
+        print_intervals(&races);
+    }
+    let result = races.iter().map(ways_to_win).product::<Integer>();
 
     println!("{result}");
 }
 
 fn read_input_2() -> Race {
-    let mut time = 0i64;
-    let mut distance = 0i64;
+    let mut time = Integer::new();
+    let mut distance = Integer::new();
     for line in stdin().lines().map(|l| l.unwrap()) {
         let mut times_str = String::new();
         let mut distance_str = String::new();
@@ -81,31 +100,33 @@ fn read_input_2() -> Race {
                 .split_ascii_whitespace()
                 .collect::<Vec<_>>()
                 .join("")
-                .parse::<i64>()
+                .parse::<Integer>()
                 .unwrap();
         } else if sscanf!(&line, "Distance: {}", distance_str).is_ok() {
             distance = distance_str
                 .split_ascii_whitespace()
                 .collect::<Vec<_>>()
                 .join("")
-                .parse::<i64>()
+                .parse::<Integer>()
                 .unwrap();
         }
     }
 
-    Race {
-        time: time,
-        distance: distance,
-    }
+    Race { time, distance }
 }
 
-fn part_two() {
+fn part_two(intervals: bool) {
     let race = read_input_2();
-    let result = ways_to_win(race);
+    if intervals {
+        print_intervals(slice::from_ref(&race));
+    }
+    let result = ways_to_win(&race);
 
     println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--intervals` also lists which hold times win each race
+    let intervals = env::args().any(|arg| arg == "--intervals");
+    part_two(intervals);
 }
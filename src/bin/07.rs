@@ -1,117 +1,157 @@
-use std::{
-    cmp::{self, Ordering},
-    collections::HashMap,
-    io::stdin,
-};
+use std::{env, io::stdin};
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+// Weakest first, so the derived order is the strength order
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
 enum HandType {
-    FiveKind(char),
-    FourKind(char),
-    FullHouse(char, char),
-    ThreeKind(char),
-    TwoPair(char, char),
-    OnePair(char),
-    HighCard(char),
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeKind,
+    FullHouse,
+    FourKind,
+    FiveKind,
 }
 
-fn strength(h: HandType) -> usize {
-    match h {
-        HandType::FiveKind(_) => 7,
-        HandType::FourKind(_) => 6,
-        HandType::FullHouse(_, _) => 5,
-        HandType::ThreeKind(_) => 4,
-        HandType::TwoPair(_, _) => 3,
-        HandType::OnePair(_) => 2,
-        HandType::HighCard(_) => 1,
-    }
+#[derive(Clone, Copy, Debug)]
+enum TieBreak {
+    // Compare the cards one by one in the order they were dealt
+    Positional,
+    // Compare the ranks of the groups, largest group first (and higher rank first among equally large ones), like
+    // poker does. Falls back to the positional order if that's still a tie
+    ByGroup,
 }
 
-impl PartialOrd for HandType {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let (strength1, strength2) = (strength(*self), strength(*other));
-
-        if strength1 != strength2 {
-            return Some(strength1.cmp(&strength2));
-        }
-        return Some(Ordering::Equal);
-    }
+#[derive(Clone, Debug)]
+struct HandRules {
+    // All the cards, weakest first
+    order: Vec<char>,
+    // Counts as whatever card makes the hand strongest, but is the weakest card when breaking ties
+    wild: Option<char>,
+    hand_size: usize,
+    tie_break: TieBreak,
 }
 
 const RANKS: &str = "23456789TJQKA";
+const RANKS_2: &str = "J23456789TQKA";
 
-fn rank_num(rank: char, ranks: &str) -> usize {
-    return ranks.find(rank).unwrap();
-}
+impl HandRules {
+    fn standard() -> HandRules {
+        HandRules {
+            order: RANKS.chars().collect(),
+            wild: None,
+            hand_size: 5,
+            tie_break: TieBreak::Positional,
+        }
+    }
 
-fn hand_type(hand: &str) -> HandType {
-    let mut counts = HashMap::new();
-    for c in hand.chars() {
-        let v = counts.entry(c).or_insert(0);
-        *v += 1;
+    fn jokers() -> HandRules {
+        HandRules {
+            order: RANKS_2.chars().collect(),
+            wild: Some('J'),
+            hand_size: 5,
+            tie_break: TieBreak::Positional,
+        }
     }
 
-    let mut counts_sorted = counts.iter().map(|(&c, &n)| (c, n)).collect::<Vec<_>>();
-    counts_sorted.sort_by_key(|&(c, cnt)| cmp::Reverse(cnt));
+    fn rank(&self, card: char) -> usize {
+        self.order
+            .iter()
+            .position(|&c| c == card)
+            .unwrap_or_else(|| panic!("{card} is not a card"))
+    }
+}
 
-    let highest_cnt = counts_sorted[0].1;
-    let highest_rank = counts_sorted[0].0;
+// The derived order compares the group sizes first - a full house [3, 2] beats three of a kind [3, 1, 1] - and then
+// the card ranks in whatever order the rules break ties in
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+struct Hand {
+    // How many cards of each kind, largest first, with the wild cards added to the largest group
+    groups: Vec<usize>,
+    tie_break: Vec<usize>,
+    cards: String,
+    // The card the wild cards ended up standing for, if there were any
+    wild_as: Option<char>,
+}
 
-    match highest_cnt {
-        5 => HandType::FiveKind(highest_rank),
-        4 => HandType::FourKind(highest_rank),
-        3 => {
-            let (second_rank, second_highest_cnt) = counts_sorted[1];
-            if second_highest_cnt == 2 {
-                return HandType::FullHouse(highest_rank, second_rank);
+impl Hand {
+    fn new(cards: &str, rules: &HandRules) -> Hand {
+        let ranks = cards.chars().map(|c| rules.rank(c)).collect::<Vec<_>>();
+        assert_eq!(ranks.len(), rules.hand_size, "{cards} has the wrong number of cards");
+
+        let wild = rules.wild.map(|c| rules.rank(c));
+        let mut counts = vec![0; rules.order.len()];
+        let mut wild_cnt = 0;
+        for &rank in &ranks {
+            if Some(rank) == wild {
+                wild_cnt += 1;
+            } else {
+                counts[rank] += 1;
             }
-            return HandType::ThreeKind(highest_rank);
         }
-        2 => {
-            let (second_rank, second_highest_cnt) = counts_sorted[1];
-            if second_highest_cnt == 2 {
-                return HandType::TwoPair(highest_rank, second_rank);
+
+        // Bucket the kinds by how many of them there are, so that walking the buckets from the top gives the groups
+        // largest first, and higher ranks first among groups of the same size
+        let mut by_size = vec![Vec::new(); rules.hand_size + 1];
+        for (rank, &cnt) in counts.iter().enumerate().rev() {
+            if cnt > 0 {
+                by_size[cnt].push(rank);
+            }
+        }
+        let mut groups = Vec::new();
+        let mut group_ranks = Vec::new();
+        for (cnt, kinds) in by_size.iter().enumerate().rev() {
+            for &rank in kinds {
+                groups.push(cnt);
+                group_ranks.push(rank);
             }
-            return HandType::OnePair(highest_rank);
         }
-        1 => HandType::HighCard(highest_rank),
-        _ => todo!(),
-    }
-}
 
-fn compare_hands(
-    hand1: &str,
-    hand2: &str,
-    hand_type_fn: &Box<dyn Fn(&str) -> HandType>,
-    rank_fn: &Box<dyn Fn(char) -> usize>,
-) -> Ordering {
-    let hand_type_cmp = hand_type_fn(hand1)
-        .partial_cmp(&hand_type_fn(hand2))
-        .unwrap();
-
-    if hand_type_cmp != Ordering::Equal {
-        return hand_type_cmp;
+        // Wild cards always do best by joining the largest group. If they're all there is, they're a group of their own
+        match groups.first_mut() {
+            Some(largest) => *largest += wild_cnt,
+            None if wild_cnt > 0 => {
+                groups.push(wild_cnt);
+                group_ranks.push(wild.unwrap());
+            }
+            None => {}
+        }
+        let wild_as = (wild_cnt > 0).then(|| rules.order[group_ranks[0]]);
+
+        let tie_break = match rules.tie_break {
+            TieBreak::Positional => ranks,
+            TieBreak::ByGroup => group_ranks.into_iter().chain(ranks).collect(),
+        };
+
+        Hand {
+            groups,
+            tie_break,
+            cards: cards.to_string(),
+            wild_as,
+        }
     }
 
-    for (i, c) in hand1.chars().enumerate() {
-        let c2 = hand2.chars().nth(i).unwrap();
-
-        if c != c2 {
-            return rank_fn(c).cmp(&rank_fn(c2));
+    fn hand_type(&self) -> HandType {
+        let second = self.groups.get(1).copied().unwrap_or(0);
+        match (self.groups[0], second) {
+            (5.., _) => HandType::FiveKind,
+            (4, _) => HandType::FourKind,
+            (3, 2..) => HandType::FullHouse,
+            (3, _) => HandType::ThreeKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
         }
     }
-    return Ordering::Equal;
 }
 
-fn rank_hands(
-    hands: &Vec<(String, i32)>,
-    hand_type_fn: Box<dyn Fn(&str) -> HandType>,
-    rank_fn: Box<dyn Fn(char) -> usize>,
-) -> Vec<(String, i32)> {
-    let mut sorted = hands.clone();
-    sorted.sort_by(|(h1, _), (h2, _)| compare_hands(h1, h2, &hand_type_fn, &rank_fn));
+fn rank_hands(hands: &[(String, i32)], rules: &HandRules) -> Vec<(Hand, i32)> {
+    let mut sorted = hands
+        .iter()
+        .map(|(cards, bid)| (Hand::new(cards, rules), *bid))
+        .collect::<Vec<_>>();
+    sorted.sort();
 
-    return sorted;
+    sorted
 }
 
 fn read_input() -> Vec<(String, i32)> {
@@ -124,79 +164,95 @@ fn read_input() -> Vec<(String, i32)> {
     hands
 }
 
-fn part_one() {
+fn total_winnings(rules: &HandRules) -> i64 {
     let hands = read_input();
 
-    let rank_fn = |c| rank_num(c, RANKS);
-    let ranked = rank_hands(&hands, Box::new(hand_type), Box::new(rank_fn));
-    let result = ranked
+    let ranked = rank_hands(&hands, rules);
+    ranked
         .iter()
         .enumerate()
-        .map(|(i, &(_, bid))| ((i + 1) as i64) * (bid as i64))
-        .sum::<i64>();
+        .map(|(i, (_, bid))| ((i + 1) as i64) * (*bid as i64))
+        .sum::<i64>()
+}
 
+fn part_one() {
+    let result = total_winnings(&HandRules::standard());
     println!("{result}");
 }
 
-const RANKS_2: &str = "J23456789TQKA";
-
-fn hand_type_2(hand: &str) -> HandType {
-    let mut counts = HashMap::new();
-    for c in hand.chars() {
-        let v = counts.entry(c).or_insert(0);
-        *v += 1;
-    }
-
-    let jokers_cnt = counts.get(&'J').map(|x| *x).unwrap_or(0);
-    counts.remove(&'J');
-
-    let mut counts_sorted = counts.iter().map(|(&c, &n)| (c, n)).collect::<Vec<_>>();
-    counts_sorted.sort_by_key(|&(c, cnt)| cmp::Reverse(cnt));
-
-    if jokers_cnt == 5 {
-        return HandType::FiveKind('J');
-    }
-
-    let highest_cnt = counts_sorted[0].1;
-    let highest_rank = counts_sorted[0].0;
-
-    match highest_cnt + jokers_cnt {
-        5 => HandType::FiveKind(highest_rank),
-        4 => HandType::FourKind(highest_rank),
-        3 => {
-            let (second_rank, second_highest_cnt) = counts_sorted[1];
-            if second_highest_cnt == 2 {
-                return HandType::FullHouse(highest_rank, second_rank);
-            }
-            return HandType::ThreeKind(highest_rank);
-        }
-        2 => {
-            let (second_rank, second_highest_cnt) = counts_sorted[1];
-            if second_highest_cnt == 2 {
-                return HandType::TwoPair(highest_rank, second_rank);
-            }
-            return HandType::OnePair(highest_rank);
-        }
-        1 => HandType::HighCard(highest_rank),
-        _ => todo!(),
-    }
+fn part_two(rules: &HandRules) {
+    let result = total_winnings(rules);
+    println!("{result}");
 }
 
-fn part_two() {
+// How many hands there are of each type, and where every hand ended up in the ranking
+fn report(rules: &HandRules) {
     let hands = read_input();
+    let ranked = rank_hands(&hands, rules);
+
+    let mut types = ranked.iter().map(|(hand, _)| hand.hand_type()).collect::<Vec<_>>();
+    types.sort();
+    types.dedup();
+    println!("Hand types:");
+    for hand_type in types.iter().rev() {
+        let count = ranked.iter().filter(|(hand, _)| hand.hand_type() == *hand_type).count();
+        println!("  {:<10} {count}", format!("{hand_type:?}"));
+    }
 
-    let rank_fn = |c| rank_num(c, RANKS_2);
-    let ranked = rank_hands(&hands, Box::new(hand_type_2), Box::new(rank_fn));
-
-    let result = ranked
-        .iter()
-        .enumerate()
-        .map(|(i, &(_, bid))| ((i + 1) as i64) * (bid as i64))
-        .sum::<i64>();
-
-    println!("{result}");
+    println!();
+    println!("{:>5}  {:<8} {:<10} {:>6} {:>9}  Wild as", "Rank", "Hand", "Type", "Bid", "Winnings");
+    let mut total = 0;
+    for (i, (hand, bid)) in ranked.iter().enumerate() {
+        let winnings = (i + 1) as i64 * *bid as i64;
+        total += winnings;
+
+        let wild_as = match hand.wild_as {
+            Some(card) => format!("{card} -> {}", hand.cards.replace(rules.wild.unwrap(), &card.to_string())),
+            None => String::new(),
+        };
+        let line = format!(
+            "{:>5}  {:<8} {:<10} {bid:>6} {winnings:>9}  {wild_as}",
+            i + 1,
+            hand.cards,
+            format!("{:?}", hand.hand_type()),
+        );
+        println!("{}", line.trim_end());
+    }
+    println!("Total winnings: {total}");
 }
 
 fn main() {
-    part_two();
+    // `--report` shows how the hands were classified and ranked instead of just the total.
+    // Part two's rules can be changed with `--order <cards, weakest first>`, `--wild <card>`, `--hand-size <n>` and
+    // `--by-group` to break ties by group ranks instead of positionally. `--no-wild` starts from part one's rules instead
+    let args = env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("{flag} needs a value")))
+    };
+
+    let mut rules = if args.iter().any(|arg| arg == "--no-wild") {
+        HandRules::standard()
+    } else {
+        HandRules::jokers()
+    };
+    if let Some(order) = value("--order") {
+        rules.order = order.chars().collect();
+    }
+    if let Some(wild) = value("--wild") {
+        rules.wild = wild.chars().next();
+    }
+    if let Some(size) = value("--hand-size") {
+        rules.hand_size = size.parse().expect("--hand-size needs a number");
+    }
+    if args.iter().any(|arg| arg == "--by-group") {
+        rules.tie_break = TieBreak::ByGroup;
+    }
+
+    if args.iter().any(|arg| arg == "--report") {
+        report(&rules);
+    } else {
+        part_two(&rules);
+    }
 }
@@ -1,5 +1,6 @@
-use std::{cmp, collections::HashMap, io::stdin};
+use std::{collections::HashMap, env, io::stdin};
 
+use advent_2023::cycles::{self, Schedule};
 use scanf::sscanf;
 
 type DestinationsMap = HashMap<String, (String, String)>;
@@ -28,119 +29,184 @@ fn read_input() -> (String, DestinationsMap) {
     (instructions, destinations)
 }
 
-fn advance_one<'a>(current: &str, instruction: char, destinations: &'a DestinationsMap) -> &'a str {
-    return match instruction {
-        'L' => &destinations[current].0,
-        'R' => &destinations[current].1,
-        _ => todo!(),
-    };
+// The network with node names replaced by indices, so a step is just two array lookups
+struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+    // Left and right destination of every node
+    next: Vec<[u32; 2]>,
+    // 0 for L and 1 for R
+    instructions: Vec<usize>,
+    // jumps[k][node] is where you end up from `node` after following all of the instructions 2^k times
+    jumps: Vec<Vec<u32>>,
 }
 
-fn part_one() {
-    let (instructions, destinations) = read_input();
+impl Network {
+    fn new(instructions: &str, destinations: &DestinationsMap) -> Network {
+        let mut names = destinations.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let next = names
+            .iter()
+            .map(|name| {
+                let (left, right) = &destinations[name];
+                [ids[left], ids[right]]
+            })
+            .collect();
+        let instructions = instructions
+            .chars()
+            .map(|c| match c {
+                'L' => 0,
+                'R' => 1,
+                _ => panic!("{c} is not a direction, expected L or R"),
+            })
+            .collect();
+
+        let mut network = Network {
+            names,
+            ids,
+            next,
+            instructions,
+            jumps: Vec::new(),
+        };
+
+        let one_pass = (0..network.names.len() as u32)
+            .map(|node| {
+                (0..network.instructions.len()).fold(node, |current, i| network.step(current, i))
+            })
+            .collect::<Vec<_>>();
+        network.jumps.push(one_pass);
+        // 2^63 passes is more than enough for any u64 number of steps
+        for k in 1..64 {
+            let previous = &network.jumps[k - 1];
+            let doubled = previous.iter().map(|&node| previous[node as usize]).collect();
+            network.jumps.push(doubled);
+        }
+
+        network
+    }
 
-    let destination = "ZZZ";
-    let mut current = "AAA";
+    fn id(&self, name: &str) -> u32 {
+        *self
+            .ids
+            .get(name)
+            .unwrap_or_else(|| panic!("there's no node called {name}"))
+    }
 
-    let mut steps = 0;
+    // Follows the `i`-th instruction from `node`
+    fn step(&self, node: u32, i: usize) -> u32 {
+        self.next[node as usize][self.instructions[i]]
+    }
 
-    'out: while current != destination {
-        for instruction in instructions.chars() {
-            current = advance_one(&current, instruction, &destinations);
-            steps += 1;
+    // Where you are after `steps` steps from `start`, in O(log(steps) + number of instructions)
+    fn position_after(&self, start: u32, steps: u64) -> u32 {
+        let len = self.instructions.len() as u64;
+        let (passes, rest) = (steps / len, (steps % len) as usize);
 
-            if current == destination {
-                break 'out;
+        let mut current = start;
+        for (k, jump) in self.jumps.iter().enumerate() {
+            if passes >> k & 1 == 1 {
+                current = jump[current as usize];
             }
         }
-    }
 
-    println!("{steps}")
+        (0..rest).fold(current, |current, i| self.step(current, i))
+    }
 }
 
-fn is_final(state: &str) -> bool {
-    return state.ends_with("Z");
-}
+fn part_one() {
+    let (instructions, destinations) = read_input();
+    let network = Network::new(&instructions, &destinations);
+
+    let destination = network.id("ZZZ");
+    let mut current = network.id("AAA");
 
-fn find_cycle_len(state: &str, instructions: &str, destinations: &DestinationsMap) -> (i32, i32) {
-    let mut current = state;
     let mut steps = 0;
 
-    let mut prev_step = -1;
-    let mut first_step = -1;
-    let mut first_final_state: &str = "";
-    let mut steps_vec = Vec::new();
-
-    'out: loop {
-        for instruction in instructions.chars() {
-            current = advance_one(current, instruction, destinations);
-            steps += 1;
-
-            if is_final(current) {
-                if prev_step == -1 {
-                    first_step = steps;
-                    first_final_state = current;
-                } else {
-                    steps_vec.push(steps - prev_step);
-
-                    if current == first_final_state {
-                        break 'out;
-                    }
-                }
-                prev_step = steps;
-            }
-        }
+    while current != destination {
+        current = network.step(current, steps % network.instructions.len());
+        steps += 1;
     }
 
-    assert!(steps_vec.len() == 1);
-    let cycle_len = steps_vec[0];
-    return (first_step, cycle_len);
+    println!("{steps}")
 }
 
-fn gcd(i1: i64, i2: i64) -> i64 {
-    let (mut d1, mut d2) = (cmp::max(i1, i2), cmp::min(i1, i2));
+fn is_final(state: &str) -> bool {
+    return state.ends_with("Z");
+}
 
-    while d2 != 0 {
-        let rem = d1 % d2;
-        d1 = d2;
-        d2 = rem;
+// The steps on which a ghost starting at `start` is on a Z node. Its state is the node together with the position in
+// the instructions, so it's bound to repeat at some point, and the Z nodes it visits from then on repeat too
+fn ghost_schedule(start: u32, network: &Network) -> Schedule {
+    let step = |&(node, i): &(u32, usize)| (network.step(node, i), (i + 1) % network.instructions.len());
+    let (mu, lambda) = cycles::find_cycle(&(start, 0), step);
+
+    let mut schedule = Schedule {
+        start: mu as i64,
+        period: lambda as i64,
+        before: Vec::new(),
+        hits: Vec::new(),
+    };
+    let mut state = (start, 0);
+    for steps in 0..mu + lambda {
+        if is_final(&network.names[state.0 as usize]) {
+            if steps < mu {
+                schedule.before.push(steps as i64);
+            } else {
+                schedule.hits.push(steps as i64);
+            }
+        }
+        state = step(&state);
     }
 
-    d1
-}
-
-fn lcm(i1: i64, i2: i64) -> i64 {
-    (i1 / gcd(i1, i2)) * i2
+    schedule
 }
 
 fn part_two() {
     let (instructions, destinations) = read_input();
+    let network = Network::new(&instructions, &destinations);
 
-    let starts = destinations
-        .keys()
+    let starts = network
+        .names
+        .iter()
         .filter(|k| k.ends_with("A"))
-        .map(|k| k.as_str())
+        .map(|k| network.id(k))
         .collect::<Vec<_>>();
 
-    let cycle_lens = starts
+    // All the ghosts are on Z nodes on the steps that are in every one of their schedules
+    let result = starts
         .iter()
-        .map(|&start| {
-            let (start, cycle_len) = find_cycle_len(start, &instructions, &destinations);
+        .map(|&start| ghost_schedule(start, &network))
+        .reduce(|a, b| a.combine(&b))
+        .and_then(|schedule| schedule.first())
+        .expect("the ghosts are never all on Z nodes at the same time");
 
-            assert_eq!(start, cycle_len);
-            return cycle_len as i64;
-        })
-        .collect::<Vec<_>>();
+    println!("{result}");
+}
 
-    let result = cycle_lens
-        .iter()
-        .map(|x| *x)
-        .reduce(|a, b| lcm(a, b))
-        .unwrap();
+fn position_after(start: &str, steps: u64) {
+    let (instructions, destinations) = read_input();
+    let network = Network::new(&instructions, &destinations);
 
-    println!("{result}");
+    let node = network.position_after(network.id(start), steps);
+    println!("{}", network.names[node as usize]);
 }
 
 fn main() {
-    part_two();
+    // `--where <node> <steps>` tells where a ghost starting at that node is after that many steps
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--where") {
+        let start = args.get(i + 1).expect("--where needs a starting node");
+        let steps = args
+            .get(i + 2)
+            .and_then(|n| n.parse().ok())
+            .expect("--where needs a number of steps");
+        position_after(start, steps);
+    } else {
+        part_two();
+    }
 }
@@ -1,64 +1,86 @@
-use std::io::stdin;
+use std::{env, io::stdin, process};
 
-fn predict_next(seq: &Vec<i32>) -> i32 {
-    let mut diffs: Vec<Vec<i32>> = Vec::new();
-    let mut current = seq.clone();
+use advent_2023::sequence::{self, Polynomial};
+use rug::Integer;
 
-    loop {
-        diffs.push(current.clone());
-        let mut current_diffs = Vec::new();
-
-        if current.len() <= 1 {
-            break;
-        }
-
-        for i in 0..current.len() - 1 {
-            current_diffs.push(current[i + 1] - current[i]);
+// Every non-empty line, along with its line number
+fn read_input() -> Vec<(usize, Vec<Integer>)> {
+    let mut result = Vec::new();
+    for (i, line) in stdin().lines().map(|l| l.unwrap()).enumerate() {
+        let seq = line
+            .split_ascii_whitespace()
+            .map(|part| part.parse::<Integer>().unwrap())
+            .collect::<Vec<_>>();
+        if !seq.is_empty() {
+            result.push((i + 1, seq));
         }
+    }
 
-        if current_diffs.iter().all(|x| *x == current_diffs[0]) {
-            let mut result = current_diffs[0];
-            for diffs_row in diffs.iter().rev() {
-                result = diffs_row.last().unwrap() + result;
-            }
-            return result;
-        }
+    result
+}
 
-        current = current_diffs.clone();
-    }
+// Fits every line, stopping at the first one that can't be fitted
+fn fit_all(input: &[(usize, Vec<Integer>)]) -> Result<Vec<(usize, Polynomial)>, String> {
+    input
+        .iter()
+        .map(|(line, seq)| match sequence::fit(seq) {
+            Ok(polynomial) => Ok((seq.len(), polynomial)),
+            Err(err) => Err(format!("Line {line}: {err}")),
+        })
+        .collect()
+}
 
-    -1
+// Rather than leaving a line out of the sum and printing a wrong total
+fn fit_input() -> Vec<(usize, Polynomial)> {
+    fit_all(&read_input()).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    })
 }
 
 fn part_one() {
-    let mut result = 0;
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        let seq = line
-            .split_ascii_whitespace()
-            .map(|part| part.parse::<i32>().unwrap())
-            .collect::<Vec<_>>();
-
-        result += predict_next(&seq);
-    }
+    let result = fit_input()
+        .iter()
+        .map(|(len, polynomial)| polynomial.forward(*len, 1).remove(0))
+        .sum::<Integer>();
 
     println!("{result}");
 }
 
 fn part_two() {
-    let mut result = 0;
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        let seq = line
-            .split_ascii_whitespace()
-            .map(|part| part.parse::<i32>().unwrap())
-            .rev()
-            .collect::<Vec<_>>();
+    let result = fit_input()
+        .iter()
+        .map(|(_, polynomial)| polynomial.backward(1).remove(0))
+        .sum::<Integer>();
+
+    println!("{result}");
+}
+
+// Sum of the values at any index, along with the degree of every sequence
+fn evaluate(index: i64) {
+    let fitted = fit_input();
 
-        result += predict_next(&seq);
+    for (len, polynomial) in &fitted {
+        eprintln!("{len} values of degree {}", polynomial.degree());
     }
+    let result = fitted
+        .iter()
+        .map(|(_, polynomial)| polynomial.at(index))
+        .sum::<Integer>();
 
     println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--at <index>` sums up the values at that index instead, with 0 being the first value given
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--at") {
+        let index = args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .expect("--at needs an index");
+        evaluate(index);
+    } else {
+        part_two();
+    }
 }
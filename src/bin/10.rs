@@ -1,5 +1,6 @@
 use std::{
     collections::{HashSet, VecDeque},
+    env, fmt, fs,
     io::stdin,
 };
 
@@ -17,16 +18,16 @@ const DOWN: Dir = Pos { x: 0, y: 1 };
 
 type Map = Vec<Vec<char>>;
 
-fn animal_position(map: &Map) -> Pos {
+fn animal_position(map: &Map) -> Option<Pos> {
     for i in 0..map.len() {
         if let Some(j) = map[i].iter().position(|c| *c == 'S') {
-            return Pos {
+            return Some(Pos {
                 x: j as i32,
                 y: i as i32,
-            };
+            });
         }
     }
-    todo!();
+    None
 }
 
 fn neighbours(pos: Pos, map: &Map) -> Vec<Pos> {
@@ -97,37 +98,28 @@ fn farthest_from(pos: Pos, map: &Map) -> (Pos, i32) {
     (max_dist_pos, max_dist)
 }
 
-fn part_one() {
+fn read_input() -> Map {
     let mut map = Vec::new();
     for line in stdin().lines().map(|l| l.unwrap()) {
         map.push(line.chars().collect::<Vec<_>>());
     }
-
-    let animal_pos = animal_position(&map);
-    let (_, dist) = farthest_from(animal_pos, &map);
-
-    println!("{dist}")
+    map
 }
 
-fn loop_points(start: Pos, map: &Map) -> HashSet<Pos> {
-    let mut q = VecDeque::new();
-    q.push_back((start, 0));
-
-    let mut visited = HashSet::new();
-    visited.insert(start);
-
-    while !q.is_empty() {
-        let (curr, d) = q.pop_front().unwrap();
-
-        for n in neighbours(curr, map) {
-            if !visited.contains(&n) {
-                q.push_back((n, d + 1));
-                visited.insert(n);
-            }
+fn part_one(verify: bool) {
+    let map = read_input();
+    let pipe_loop = match PipeLoop::new(&map) {
+        Ok(pipe_loop) => pipe_loop,
+        Err(err) => {
+            println!("{err}");
+            return;
         }
+    };
+    if verify {
+        pipe_loop.verify();
     }
 
-    visited
+    println!("{}", pipe_loop.farthest_distance())
 }
 
 fn direction(p1: Pos, p2: Pos) -> Dir {
@@ -166,35 +158,6 @@ fn left(p: Pos, dir: Dir) -> Pos {
     }
 }
 
-fn generate_path(start: Pos, loop_pts: &HashSet<Pos>, map: &Map) -> Vec<Pos> {
-    let mut path = Vec::new();
-    let mut current = start;
-    let mut visited = HashSet::new();
-
-    while path.len() < loop_pts.len() {
-        let next = neighbours(current, &map)
-            .iter()
-            .filter(|&p| !visited.contains(p))
-            .map(|&p| p)
-            .collect::<Vec<_>>();
-
-        if next.len() > 1 {
-            assert!(current == start);
-        }
-
-        path.push(current);
-        visited.insert(current);
-
-        if !next.is_empty() {
-            current = next[0];
-        } else {
-            assert!(path.len() == loop_pts.len());
-        }
-    }
-
-    path
-}
-
 fn in_bounds(p: Pos, map: &Map) -> bool {
     return p.x >= 0 && p.x < map[0].len() as i32 && p.y >= 0 && p.y < map.len() as i32;
 }
@@ -299,65 +262,305 @@ fn count_inside_2(loop_pts: &HashSet<Pos>, map: &Map) -> HashSet<Pos> {
     res
 }
 
-fn replace_starting_pos(path: &Vec<Pos>, map: &mut Map) {
-    let start = path[0];
-    let (d1, d2) = (
-        direction(path[path.len() - 1], path[0]),
-        direction(path[0], path[1]),
-    );
+const PIPES: &str = "|-LJ7F";
+
+fn pipe_ends(c: char) -> Option<[Dir; 2]> {
+    match c {
+        '|' => Some([UP, DOWN]),
+        '-' => Some([LEFT, RIGHT]),
+        'L' => Some([UP, RIGHT]),
+        'J' => Some([UP, LEFT]),
+        '7' => Some([DOWN, LEFT]),
+        'F' => Some([DOWN, RIGHT]),
+        _ => None,
+    }
+}
 
-    let c = match d1 {
-        UP => match d2 {
-            RIGHT => 'F',
-            UP => '|',
-            LEFT => '7',
-            _ => todo!(),
-        },
-        DOWN => match d2 {
-            RIGHT => 'L',
-            DOWN => '|',
-            LEFT => 'J',
-            _ => todo!(),
-        },
-        LEFT => match d2 {
-            UP => 'L',
-            DOWN => 'F',
-            LEFT => '-',
-            _ => todo!(),
-        },
-        RIGHT => match d2 {
-            UP => 'J',
-            DOWN => '7',
-            RIGHT => '-',
-            _ => todo!(),
-        },
-        _ => todo!(),
-    };
+fn opposite(dir: Dir) -> Dir {
+    Pos {
+        x: -dir.x,
+        y: -dir.y,
+    }
+}
 
-    map[start.y as usize][start.x as usize] = c;
+// Pipe shapes the start tile could have - ones where both ends lead into a pipe that connects back to it
+fn start_candidates(start: Pos, map: &Map) -> Vec<char> {
+    PIPES
+        .chars()
+        .filter(|&shape| {
+            pipe_ends(shape).unwrap().iter().all(|&dir| {
+                let next = advance(start, dir);
+                in_bounds(next, map)
+                    && pipe_ends(map[next.y as usize][next.x as usize])
+                        .is_some_and(|ends| ends.contains(&opposite(dir)))
+            })
+        })
+        .collect()
 }
 
-fn part_two() {
-    let mut map = Vec::new();
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        map.push(line.chars().collect::<Vec<_>>());
+// Follows the pipes from `start` until getting back to it. None if they lead off the map or into a tile that
+// doesn't connect back
+fn trace_loop(start: Pos, map: &Map) -> Option<Vec<Pos>> {
+    let mut path = vec![start];
+    let start_ends = pipe_ends(map[start.y as usize][start.x as usize])?;
+    let mut dir = start_ends[0];
+    loop {
+        let next = advance(*path.last().unwrap(), dir);
+        // Only a loop if it comes back in through the start's other end, not just through any side of it
+        if next == start {
+            return start_ends.contains(&opposite(dir)).then_some(path);
+        }
+        if !in_bounds(next, map) {
+            return None;
+        }
+
+        let ends = pipe_ends(map[next.y as usize][next.x as usize])?;
+        let back = opposite(dir);
+        dir = match ends {
+            [a, b] if a == back => b,
+            [a, b] if b == back => a,
+            _ => return None,
+        };
+        path.push(next);
+    }
+}
+
+#[derive(Debug)]
+enum StartError {
+    NoAnimal,
+    // None of the shapes the start tile could have (possibly none at all) closes a loop
+    NoLoop { candidates: Vec<char> },
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StartError::NoAnimal => write!(f, "no starting position on the map"),
+            StartError::NoLoop { candidates } if candidates.is_empty() => {
+                write!(f, "the starting position doesn't connect to two pipes")
+            }
+            StartError::NoLoop { candidates } => write!(
+                f,
+                "none of the possible starting pipes ({}) closes a loop",
+                candidates.iter().collect::<String>()
+            ),
+        }
+    }
+}
+
+// The loop the animal is in, along with the map where its starting tile is replaced by the pipe it's hiding
+struct PipeLoop {
+    path: Vec<Pos>,
+    points: HashSet<Pos>,
+    map: Map,
+}
+
+impl PipeLoop {
+    // Tries every shape the start tile could have, and keeps the one that closes the longest loop
+    fn new(map: &Map) -> Result<PipeLoop, StartError> {
+        let start = animal_position(map).ok_or(StartError::NoAnimal)?;
+        let candidates = start_candidates(start, map);
+
+        let mut best: Option<(Vec<Pos>, Map)> = None;
+        for &shape in &candidates {
+            let mut map = map.clone();
+            map[start.y as usize][start.x as usize] = shape;
+            if let Some(path) = trace_loop(start, &map) {
+                if best.as_ref().is_none_or(|(best_path, _)| path.len() > best_path.len()) {
+                    best = Some((path, map));
+                }
+            }
+        }
+
+        let (path, map) = best.ok_or(StartError::NoLoop { candidates })?;
+        let points = path.iter().copied().collect();
+        Ok(PipeLoop { path, points, map })
+    }
+
+    fn len(&self) -> usize {
+        self.path.len()
     }
 
-    let animal_pos = animal_position(&map);
-    let loop_pts = loop_points(animal_pos, &map);
+    fn farthest_distance(&self) -> usize {
+        self.len() / 2
+    }
 
-    let path = generate_path(animal_pos, &loop_pts, &map);
-    replace_starting_pos(&path, &mut map);
+    // Area of the polygon through the tile centres (shoelace formula), and then Pick's theorem gives the number
+    // of tiles strictly inside it: A = I + B/2 - 1, with every tile of the loop being a boundary point
+    fn enclosed(&self) -> usize {
+        let twice_area = (0..self.len())
+            .map(|i| {
+                let (p1, p2) = (self.path[i], self.path[(i + 1) % self.len()]);
+                p1.x as i64 * p2.y as i64 - p2.x as i64 * p1.y as i64
+            })
+            .sum::<i64>()
+            .abs();
+
+        (twice_area / 2 - self.len() as i64 / 2 + 1) as usize
+    }
 
-    for clockwise in [true, false] {
-        let (positions, outside) = count_inside(&path, clockwise, &loop_pts, &map);
-        if !outside {
-            println!("{}", positions.len());
-            break;
+    // Flood fill to one side of the path, whichever side doesn't leak outside the map
+    fn inside_points(&self) -> HashSet<Pos> {
+        for clockwise in [true, false] {
+            let (positions, outside) = count_inside(&self.path, clockwise, &self.points, &self.map);
+            if !outside {
+                return positions;
+            }
         }
+        unreachable!("loop has no inside");
+    }
+
+    fn enclosed_flood_fill(&self) -> usize {
+        self.inside_points().len()
+    }
+
+    fn enclosed_scanline(&self) -> usize {
+        count_inside_2(&self.points, &self.map).len()
+    }
+
+    fn verify(&self) {
+        let start = self.path[0];
+        let (_, bfs_dist) = farthest_from(start, &self.map);
+        assert_eq!(bfs_dist as usize, self.farthest_distance(), "farthest distance");
+
+        let enclosed = self.enclosed();
+        assert_eq!(self.enclosed_flood_fill(), enclosed, "flood fill vs shoelace");
+        assert_eq!(self.enclosed_scanline(), enclosed, "scanline vs shoelace");
+        println!("All methods agree: loop length {}, {} tiles enclosed", self.len(), enclosed);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Loop(char),
+    Start(char),
+    Inside,
+    Outside,
+}
+
+fn box_drawing(c: char) -> char {
+    match c {
+        '|' => '│',
+        '-' => '─',
+        'L' => '└',
+        'J' => '┘',
+        '7' => '┐',
+        'F' => '┌',
+        _ => unreachable!("{c} is not a pipe"),
+    }
+}
+
+// The map with only the loop left, drawn with box-drawing characters, and everything else marked as being
+// inside or outside of it
+fn render_tiles(pipe_loop: &PipeLoop) -> Vec<Vec<Tile>> {
+    let inside = pipe_loop.inside_points();
+    let start = pipe_loop.path[0];
+
+    let mut result = Vec::new();
+    for (y, line) in pipe_loop.map.iter().enumerate() {
+        let mut row = Vec::new();
+        for (x, &c) in line.iter().enumerate() {
+            let pos = Pos {
+                x: x as i32,
+                y: y as i32,
+            };
+            row.push(if pos == start {
+                Tile::Start(box_drawing(c))
+            } else if pipe_loop.points.contains(&pos) {
+                Tile::Loop(box_drawing(c))
+            } else if inside.contains(&pos) {
+                Tile::Inside
+            } else {
+                Tile::Outside
+            });
+        }
+        result.push(row);
+    }
+    result
+}
+
+fn render_ansi(pipe_loop: &PipeLoop) -> String {
+    let mut result = String::new();
+    for row in render_tiles(pipe_loop) {
+        for tile in row {
+            result += &match tile {
+                Tile::Loop(c) => format!("\x1b[1;97m{c}"),
+                Tile::Start(c) => format!("\x1b[1;93m{c}"),
+                Tile::Inside => "\x1b[42m ".to_string(),
+                Tile::Outside => "\x1b[44m ".to_string(),
+            };
+            result += "\x1b[0m";
+        }
+        result.push('\n');
+    }
+    result
+}
+
+fn render_html(pipe_loop: &PipeLoop) -> String {
+    let mut result = String::from(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><style>\n\
+         pre { font-family: monospace; line-height: 1; background: #223; color: #eee; }\n\
+         .start { color: #fd4; font-weight: bold; }\n\
+         .inside { background: #3a6; }\n\
+         .outside { background: #335; }\n\
+         </style></head><body><pre>\n",
+    );
+    for row in render_tiles(pipe_loop) {
+        for tile in row {
+            result += &match tile {
+                Tile::Loop(c) => c.to_string(),
+                Tile::Start(c) => format!("<span class=\"start\">{c}</span>"),
+                Tile::Inside => "<span class=\"inside\"> </span>".to_string(),
+                Tile::Outside => "<span class=\"outside\"> </span>".to_string(),
+            };
+        }
+        result.push('\n');
+    }
+    result += "</pre></body></html>\n";
+    result
+}
+
+fn part_two(verify: bool) {
+    let map = read_input();
+    let pipe_loop = match PipeLoop::new(&map) {
+        Ok(pipe_loop) => pipe_loop,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    if verify {
+        pipe_loop.verify();
+    }
+
+    println!("{}", pipe_loop.enclosed());
+}
+
+fn render(html_path: Option<&String>) {
+    let map = read_input();
+    let pipe_loop = match PipeLoop::new(&map) {
+        Ok(pipe_loop) => pipe_loop,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    match html_path {
+        Some(path) => fs::write(path, render_html(&pipe_loop)).expect("can't write html"),
+        None => print!("{}", render_ansi(&pipe_loop)),
     }
 }
 
 fn main() {
-    part_two();
+    // `--verify` checks that the different ways of measuring the loop all agree (10_example_6.input has an S touching
+    // four pipes, only one pair of which closes the loop). `--render` draws the loop in the terminal instead of
+    // solving, `--html <file>` writes the same drawing as a web page
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--html") {
+        render(Some(args.get(i + 1).expect("--html needs a file name")));
+    } else if args.iter().any(|arg| arg == "--render") {
+        render(None);
+    } else {
+        part_two(args.iter().any(|arg| arg == "--verify"));
+    }
 }
@@ -1,4 +1,4 @@
-use std::{cmp, collections::HashSet, io::stdin};
+use std::{collections::HashSet, env, io::stdin};
 
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
 struct Pos {
@@ -60,70 +60,77 @@ fn galaxy_positions(map: &Vec<Vec<char>>) -> Vec<Pos> {
     galaxies
 }
 
-fn dist(
-    g1: Pos,
-    g2: Pos,
-    empty_rows: &HashSet<usize>,
-    empty_cols: &HashSet<usize>,
-    empty_size: i32,
-) -> i64 {
-    let mut d = ((g1.x - g2.x).abs() + (g1.y - g2.y).abs()) as i64;
-
-    let (min_y, max_y) = (cmp::min(g1.y, g2.y), cmp::max(g1.y, g2.y));
-    for r in empty_rows {
-        let r = *r as i32;
-        if min_y < r && r < max_y {
-            d += (empty_size - 1) as i64;
-        }
+// For every index, how many of the empty rows/columns come before it
+fn empty_before(empty: &HashSet<usize>, len: usize) -> Vec<i64> {
+    let mut result = vec![0; len + 1];
+    for i in 0..len {
+        result[i + 1] = result[i] + empty.contains(&i) as i64;
     }
+    result
+}
 
-    let (min_x, max_x) = (cmp::min(g1.x, g2.x), cmp::max(g1.x, g2.x));
-    for c in empty_cols {
-        let c = *c as i32;
-        if min_x < c && c < max_x {
-            d += (empty_size - 1) as i64;
-        }
-    }
+type Expansion = (Vec<i64>, Vec<i64>);
+
+fn expansion(map: &[Vec<char>], empty_rows: &HashSet<usize>, empty_cols: &HashSet<usize>) -> Expansion {
+    (
+        empty_before(empty_rows, map.len()),
+        empty_before(empty_cols, map[0].len()),
+    )
+}
+
+// Sum of |a - b| over all pairs - once sorted, the i-th value is added i times and subtracted (n - 1 - i) times
+fn sum_of_differences(mut values: Vec<i64>) -> i64 {
+    values.sort();
+    let n = values.len() as i64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| v * (2 * i as i64 - (n - 1)))
+        .sum()
+}
 
-    d
+// Sum of the distances over all pairs of galaxies. The distance splits into independent x and y parts, so each axis is
+// summed up on its own, with every coordinate moved to where it ends up after the expansion
+fn total_dist(galaxies: &[Pos], expansion: &Expansion, empty_size: i64) -> i64 {
+    let (rows_before, cols_before) = expansion;
+    let xs = galaxies
+        .iter()
+        .map(|g| g.x as i64 + cols_before[g.x as usize] * (empty_size - 1))
+        .collect();
+    let ys = galaxies
+        .iter()
+        .map(|g| g.y as i64 + rows_before[g.y as usize] * (empty_size - 1))
+        .collect();
+
+    sum_of_differences(xs) + sum_of_differences(ys)
 }
 
 fn part_one() {
     let (map, empty_rows, empty_cols) = read_input();
     let galaxies = galaxy_positions(&map);
+    let expansion = expansion(&map, &empty_rows, &empty_cols);
 
-    let mut result = 0;
-    for i in 0..galaxies.len() {
-        for j in i + 1..galaxies.len() {
-            let d = dist(galaxies[i], galaxies[j], &empty_rows, &empty_cols, 2);
-            result += d;
-        }
-    }
-
+    let result = total_dist(&galaxies, &expansion, 2);
     println!("{result}")
 }
 
-fn part_two() {
+fn part_two(empty_size: i64) {
     let (map, empty_rows, empty_cols) = read_input();
     let galaxies = galaxy_positions(&map);
+    let expansion = expansion(&map, &empty_rows, &empty_cols);
 
-    let mut result = 0;
-    for i in 0..galaxies.len() {
-        for j in i + 1..galaxies.len() {
-            let d = dist(
-                galaxies[i],
-                galaxies[j],
-                &empty_rows,
-                &empty_cols,
-                1_000_000,
-            );
-            result += d;
-        }
-    }
-
+    let result = total_dist(&galaxies, &expansion, empty_size);
     println!("{result}")
 }
 
 fn main() {
-    part_two();
+    // `--expansion <n>` to make each empty row/column n times as big, instead of a million times
+    let args = env::args().collect::<Vec<_>>();
+    let empty_size = args
+        .iter()
+        .position(|arg| arg == "--expansion")
+        .map(|i| args.get(i + 1).expect("--expansion needs a value").parse().unwrap())
+        .unwrap_or(1_000_000);
+
+    part_two(empty_size);
 }
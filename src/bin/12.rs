@@ -1,134 +1,267 @@
-use memoize::memoize;
+use rand::Rng;
+use rug::Integer;
 use scanf::sscanf;
-use std::{io::stdin, vec};
+use std::{env, io::stdin};
 
-#[memoize]
-fn valid_combinations(line: String, groups: Vec<i32>, in_group: bool) -> i64 {
-    if line.is_empty() {
-        let ok = groups.len() == 0 || (groups.len() == 1 && groups[0] == 0);
-        return if ok { 1 } else { 0 };
+struct Record {
+    springs: String,
+    groups: Vec<usize>,
+}
+
+impl Record {
+    fn parse(line: &str) -> Option<Record> {
+        let (mut springs, mut groups_str) = (String::new(), String::new());
+        sscanf!(line, "{} {}", springs, groups_str).ok()?;
+        let groups = groups_str
+            .split(",")
+            .map(|part| part.parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+
+        Some(Record { springs, groups })
     }
 
-    let c = line.chars().nth(0).unwrap();
-    let rest = line[1..].to_string();
+    fn unfold(&self, factor: usize) -> Record {
+        Record {
+            springs: vec![self.springs.as_str(); factor].join("?"),
+            groups: self.groups.repeat(factor),
+        }
+    }
 
-    match c {
-        '.' => {
-            if in_group {
-                if groups[0] != 0 {
-                    return 0;
-                }
+    // Surround the input and the desired pattern with "."
+    // to account for leading/trailing "."-s (those don't change the groups)
+    fn padded(&self) -> (Vec<char>, Vec<char>) {
+        let desired = ".".to_string()
+            + &self
+                .groups
+                .iter()
+                .map(|n| "#".repeat(*n))
+                .collect::<Vec<_>>()
+                .join(".")
+            + ".";
+        let line = ".".to_string() + &self.springs + ".";
 
-                return valid_combinations(rest, groups[1..].to_vec(), false);
-            }
-            return valid_combinations(rest, groups, false);
+        (line.chars().collect(), desired.chars().collect())
+    }
+
+    // Only ever keeps two rows of the table around, so it's fine for huge unfolded records too
+    fn count(&self) -> Integer {
+        let (line, desired) = self.padded();
+
+        let mut row = first_row(desired.len());
+        for &have in &line {
+            row = next_row(&row, have, &desired);
         }
-        '#' => {
-            if groups.len() > 0 && groups[0] > 0 {
-                let mut new_groups = groups.clone();
-                new_groups[0] -= 1;
 
-                return valid_combinations(rest, new_groups, true);
-            }
+        row[desired.len()].clone()
+    }
 
-            return 0;
+    fn table(&self) -> Table {
+        let (line, desired) = self.padded();
+
+        let mut matches = vec![first_row(desired.len())];
+        for &have in &line {
+            matches.push(next_row(matches.last().unwrap(), have, &desired));
         }
-        '?' => {
-            let line1 = ".".to_string() + rest.as_str();
-            let line2 = "#".to_string() + rest.as_str();
-            let res = valid_combinations(line1, groups.clone(), in_group)
-                + valid_combinations(line2, groups, in_group);
 
-            return res;
+        Table {
+            line,
+            desired,
+            matches,
         }
-        _ => todo!(),
     }
 }
 
-fn possibilities(line: &str, groups: &Vec<i32>) -> i64 {
-    return valid_combinations(line.to_string(), groups.clone(), false);
+// matches[i][j] is how many ways we can match line[..i] to desired[..j]
+struct Table {
+    line: Vec<char>,
+    desired: Vec<char>,
+    matches: Vec<Vec<Integer>>,
 }
 
-fn possibilities_dp(line: &str, groups: &Vec<i32>) -> i64 {
-    // Surround the input and the desired pattern with "."
-    // to account for leading/trailing "."-s (those don't change the groups)
-    let desired = ".".to_string()
-        + &groups
-            .iter()
-            .map(|n| "#".repeat(*n as usize))
-            .collect::<Vec<_>>()
-            .join(".")
-        + ".";
+fn first_row(m: usize) -> Vec<Integer> {
+    // If both are empty, they match
+    let mut row = vec![Integer::new(); m + 1];
+    row[0] = Integer::from(1);
+    row
+}
 
-    let line = ".".to_string() + line + ".";
+// The row for one more character of the line
+fn next_row(previous: &[Integer], have: char, desired: &[char]) -> Vec<Integer> {
+    let mut row = vec![Integer::new(); desired.len() + 1];
+    for j in 1..=desired.len() {
+        let want = desired[j - 1];
+        let matching = have == want || have == '?';
 
-    let n = line.len();
-    let m = desired.len();
+        if matching && want == '#' {
+            row[j] = previous[j - 1].clone();
+        }
+        if matching && want == '.' {
+            // The '.' in `want` can match one or more '.'-s in `have`, so we can either "consume" it
+            // or not
+            row[j] = Integer::from(&previous[j - 1] + &previous[j]);
+        }
+    }
 
-    // how many ways we can match line[..i] to desired[..j]
-    let mut matches = vec![vec![0; m + 1]; n + 1];
-    // If both are empty, they match
-    matches[0][0] = 1;
+    row
+}
 
-    let (chars1, chars2) = (line.as_bytes(), desired.as_bytes());
+impl Table {
+    // The ways to get to matches[i][j], as (i - 1, j') and the spring the (i - 1)-th character becomes. Each
+    // arrangement is exactly one path from the end of the table back to the start
+    fn predecessors(&self, i: usize, j: usize) -> Vec<((usize, usize), char)> {
+        let want = self.desired[j - 1];
+        let have = self.line[i - 1];
+        if have != want && have != '?' {
+            return Vec::new();
+        }
 
-    for i in 1..=n {
-        for j in 1..=m {
-            let (have, want) = (chars1[i - 1] as char, chars2[j - 1] as char);
-            let matching = have == want || have == '?';
+        let mut result = vec![((i - 1, j - 1), want)];
+        if want == '.' {
+            result.push(((i - 1, j), want));
+        }
+        result
+            .into_iter()
+            .filter(|((i, j), _)| self.matches[*i][*j] != 0)
+            .collect()
+    }
 
-            if matching && want == '#' {
-                matches[i][j] = matches[i - 1][j - 1];
-            }
-            if matching && want == '.' {
-                // The '.' in `want` can match one or more '.'-s in `have`, so we can either "consume" it
-                // or not
-                matches[i][j] += matches[i - 1][j - 1] + matches[i - 1][j];
-            }
+    // Strips the padding again
+    fn arrangement(&self, reversed: &[char]) -> String {
+        let springs = reversed.iter().rev().collect::<String>();
+        springs[1..springs.len() - 1].to_string()
+    }
+
+    // Up to `limit` arrangements, in no particular order
+    fn enumerate(&self, limit: usize) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut reversed = Vec::new();
+        self.enumerate_from(self.line.len(), self.desired.len(), &mut reversed, limit, &mut result);
+        result
+    }
+
+    fn enumerate_from(&self, i: usize, j: usize, reversed: &mut Vec<char>, limit: usize, result: &mut Vec<String>) {
+        if result.len() >= limit {
+            return;
+        }
+        if i == 0 {
+            result.push(self.arrangement(reversed));
+            return;
+        }
+
+        for ((pi, pj), c) in self.predecessors(i, j) {
+            reversed.push(c);
+            self.enumerate_from(pi, pj, reversed, limit, result);
+            reversed.pop();
         }
     }
 
-    matches[n][m]
+    // Walks back from the end, picking each step with probability proportional to how many arrangements go through
+    // it, which makes every arrangement equally likely
+    fn sample(&self, rng: &mut impl Rng) -> Option<String> {
+        let (mut i, mut j) = (self.line.len(), self.desired.len());
+        if self.matches[i][j] == 0 {
+            return None;
+        }
+
+        let mut reversed = Vec::new();
+        while i > 0 {
+            let options = self.predecessors(i, j);
+            let mut pick = random_below(&self.matches[i][j], rng);
+            for ((pi, pj), c) in options {
+                if pick < self.matches[pi][pj] {
+                    reversed.push(c);
+                    (i, j) = (pi, pj);
+                    break;
+                }
+                pick -= &self.matches[pi][pj];
+            }
+        }
+
+        Some(self.arrangement(&reversed))
+    }
 }
 
-fn part_one() {
-    let mut result = 0;
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        let (mut records, mut groups_str) = (String::new(), String::new());
-        if sscanf!(&line, "{} {}", records, groups_str).is_ok() {
-            let groups = groups_str
-                .split(",")
-                .map(|part| part.parse::<i32>().unwrap())
-                .collect::<Vec<_>>();
+// Uniform in 0..n, by drawing as many random bits as n has until the number is small enough
+fn random_below(n: &Integer, rng: &mut impl Rng) -> Integer {
+    let bits = n.significant_bits();
+    loop {
+        let mut result = Integer::new();
+        for _ in 0..bits.div_ceil(32) {
+            result = (result << 32) + rng.gen::<u32>();
+        }
+        result >>= bits.div_ceil(32) * 32 - bits;
 
-            result += possibilities_dp(&records, &groups);
+        if result < *n {
+            return result;
         }
     }
+}
+
+fn read_input() -> Vec<Record> {
+    stdin()
+        .lines()
+        .map(|l| l.unwrap())
+        .filter_map(|line| Record::parse(&line))
+        .collect()
+}
+
+fn part_one() {
+    let result = read_input().iter().map(Record::count).sum::<Integer>();
 
     println!("{result}");
 }
 
-fn part_two() {
-    let mut result = 0;
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        let (mut records, mut groups_str) = (String::new(), String::new());
-        if sscanf!(&line, "{} {}", records, groups_str).is_ok() {
-            let times = 5;
-            let records_new: String = vec![records; times].join("?").into();
-            let groups_new: String = vec![groups_str; times].join(",").into();
+fn part_two(factor: usize) {
+    let result = read_input()
+        .iter()
+        .map(|record| record.unfold(factor).count())
+        .sum::<Integer>();
 
-            let groups = groups_new
-                .split(",")
-                .map(|part| part.parse::<i32>().unwrap())
-                .collect::<Vec<_>>();
+    println!("{result}");
+}
 
-            result += possibilities_dp(&records_new, &groups);
+// Lists up to `limit` arrangements of one line, or `samples` random ones if that's given
+fn show(line: usize, factor: usize, limit: usize, samples: Option<usize>) {
+    let records = read_input();
+    let record = records
+        .get(line - 1)
+        .unwrap_or_else(|| panic!("there are only {} lines", records.len()))
+        .unfold(factor);
+    let table = record.table();
+
+    println!("{} {:?}: {} arrangements", record.springs, record.groups, record.count());
+    match samples {
+        Some(samples) => {
+            let mut rng = rand::thread_rng();
+            for _ in 0..samples {
+                if let Some(arrangement) = table.sample(&mut rng) {
+                    println!("{arrangement}");
+                }
+            }
+        }
+        None => {
+            for arrangement in table.enumerate(limit) {
+                println!("{arrangement}");
+            }
         }
     }
-
-    println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--unfold <n>` changes how many times the records are repeated. `--show <line>` lists the arrangements of
+    // that line (up to 1000 of them), and `--sample <n>` draws n random ones instead
+    let args = env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter().position(|arg| arg == flag).map(|i| {
+            args.get(i + 1)
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or_else(|| panic!("{flag} needs a number"))
+        })
+    };
+
+    let factor = value("--unfold").unwrap_or(5);
+    match value("--show") {
+        Some(line) => show(line, factor, 1000, value("--sample")),
+        None => part_two(factor),
+    }
 }
@@ -1,6 +1,8 @@
-use std::io::stdin;
+use advent_2023::bitgrid::BitGrid;
+use std::{env, io::stdin};
 
-type Map = Vec<Vec<char>>;
+// Rocks are the set cells
+type Map = BitGrid;
 
 fn read_input() -> Vec<Map> {
     let mut result = Vec::new();
@@ -8,7 +10,7 @@ fn read_input() -> Vec<Map> {
     for line in stdin().lines().map(|l| l.unwrap()) {
         if line.is_empty() {
             if !current_map.is_empty() {
-                result.push(current_map);
+                result.push(BitGrid::from_chars(&current_map, '#'));
                 current_map = Vec::new();
             }
             continue;
@@ -18,74 +20,155 @@ fn read_input() -> Vec<Map> {
     }
 
     if !current_map.is_empty() {
-        result.push(current_map);
+        result.push(BitGrid::from_chars(&current_map, '#'));
     }
 
     result
 }
 
-fn differences_if_mirror_col(col: usize, map: &Vec<Vec<char>>) -> i32 {
-    let (mut before, mut after) = (col - 1, col);
-    let mut diffs = 0;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Axis {
+    // Mirror between columns `n - 1` and `n`
+    Vertical(usize),
+    // Mirror between rows `n - 1` and `n`
+    Horizontal(usize),
+}
 
-    loop {
-        for row in 0..map.len() {
-            if map[row][before] != map[row][after] {
-                diffs += 1;
-            }
+impl Axis {
+    fn score(&self) -> usize {
+        match self {
+            Axis::Vertical(col) => *col,
+            Axis::Horizontal(row) => 100 * row,
         }
-        if before == 0 || after == map[0].len() - 1 {
-            break;
-        }
-        before -= 1;
-        after += 1;
     }
-    return diffs;
 }
 
-fn differences_if_mirror_row(row: usize, map: &Vec<Vec<char>>) -> i32 {
-    let (mut before, mut after) = (row - 1, row);
-    let mut diffs = 0;
+// (row, col)
+type Cell = (usize, usize);
 
-    loop {
-        for col in 0..map[0].len() {
-            if map[before][col] != map[after][col] {
-                diffs += 1;
-            }
+#[derive(Debug)]
+struct Reflection {
+    axis: Axis,
+    // Pairs of cells that would have to match for the mirror to be perfect. Fixing either one of a pair removes
+    // the smudge, the first one is the cell closer to the mirror line on the top/left side
+    smudges: Vec<(Cell, Cell)>,
+}
+
+// Pairs of cells that differ across the given mirror, giving up as soon as there are more than `limit` of them.
+// Each pair of mirrored lines is compared in one go, the set bits of their XOR are the differing cells
+fn differences(axis: Axis, map: &Map, limit: usize) -> Option<Vec<(Cell, Cell)>> {
+    let mut result = Vec::new();
+
+    let (n, lines) = match axis {
+        Axis::Vertical(col) => (col, map.cols()),
+        Axis::Horizontal(row) => (row, map.rows()),
+    };
+    for offset in 0..n.min(lines.len() - n) {
+        let (before, after) = (n - 1 - offset, n + offset);
+        let mut diff = lines[before] ^ lines[after];
+        if result.len() + diff.count_ones() as usize > limit {
+            return None;
         }
-        if before == 0 || after == map.len() - 1 {
-            break;
+
+        while diff != 0 {
+            let i = diff.trailing_zeros() as usize;
+            result.push(match axis {
+                Axis::Vertical(_) => ((i, before), (i, after)),
+                Axis::Horizontal(_) => ((before, i), (after, i)),
+            });
+            diff &= diff - 1;
         }
-        before -= 1;
-        after += 1;
     }
-    return diffs;
+
+    Some(result)
 }
 
-fn find_symmetry_score(m: &Map, diffs: i32) -> i32 {
-    let mut result = 0;
-    for col in 1..m[0].len() {
-        if differences_if_mirror_col(col, &m) == diffs {
-            result += col as i32;
+// All mirror lines that need at most `max_smudges` cells fixed. The k-th entry holds the mirrors that are off by
+// exactly k cells
+fn reflections(map: &Map, max_smudges: usize) -> Vec<Vec<Reflection>> {
+    let mut result = (0..=max_smudges).map(|_| Vec::new()).collect::<Vec<_>>();
+    let axes = (1..map.width())
+        .map(Axis::Vertical)
+        .chain((1..map.height()).map(Axis::Horizontal));
+
+    for axis in axes {
+        if let Some(smudges) = differences(axis, map, max_smudges) {
+            result[smudges.len()].push(Reflection { axis, smudges });
         }
     }
 
-    for row in 1..m.len() {
-        if differences_if_mirror_row(row, &m) == diffs {
-            result += 100 * row as i32;
+    result
+}
+
+fn find_symmetry_score(m: &Map, diffs: usize) -> usize {
+    reflections(m, diffs)[diffs]
+        .iter()
+        .map(|reflection| reflection.axis.score())
+        .sum()
+}
+
+// Draws the pattern with the mirror line in yellow, the smudges already fixed and highlighted in red, and their
+// mirror images underlined
+fn render(map: &Map, reflection: &Reflection) -> String {
+    let mut map = map.clone();
+    for ((row, col), _) in &reflection.smudges {
+        map.set(*row, *col, !map.get(*row, *col));
+    }
+
+    let style = |cell: Cell| {
+        if reflection.smudges.iter().any(|(fixed, _)| *fixed == cell) {
+            "\x1b[1;97;41m"
+        } else if reflection.smudges.iter().any(|(_, image)| *image == cell) {
+            "\x1b[4;91m"
+        } else {
+            ""
+        }
+    };
+
+    let mut result = String::new();
+    for row in 0..map.height() {
+        if reflection.axis == Axis::Horizontal(row) {
+            result += &format!("\x1b[93m{}\x1b[0m\n", "─".repeat(map.width()));
+        }
+        for col in 0..map.width() {
+            if reflection.axis == Axis::Vertical(col) {
+                result += "\x1b[93m│\x1b[0m";
+            }
+            let c = if map.get(row, col) { '#' } else { '.' };
+            result += &format!("{}{c}\x1b[0m", style((row, col)));
         }
+        result += "\n";
     }
 
     result
 }
 
+fn show(max_smudges: usize) {
+    let input = read_input();
+
+    for (i, map) in input.iter().enumerate() {
+        for (k, found) in reflections(map, max_smudges).iter().enumerate() {
+            for reflection in found {
+                let cells = reflection
+                    .smudges
+                    .iter()
+                    .map(|((row, col), _)| format!("({row}, {col})"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("Pattern {i}: {:?} with {k} smudge(s) {cells}", reflection.axis);
+                println!("{}", render(map, reflection));
+            }
+        }
+    }
+}
+
 fn part_one() {
     let input = read_input();
 
     let result = input
         .iter()
         .map(|part| find_symmetry_score(part, 0))
-        .sum::<i32>();
+        .sum::<usize>();
 
     println!("{result}");
 }
@@ -96,11 +179,17 @@ fn part_two() {
     let result = input
         .iter()
         .map(|part| find_symmetry_score(part, 1))
-        .sum::<i32>();
+        .sum::<usize>();
 
     println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--show <k>` lists every mirror that needs at most k cells fixed, drawn with the fixed cells highlighted
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--show") {
+        show(args.get(i + 1).and_then(|k| k.parse().ok()).unwrap_or(1));
+    } else {
+        part_two();
+    }
 }
@@ -1,153 +1,134 @@
-use std::{collections::HashMap, io::stdin};
-
-type Map = Vec<Vec<char>>;
+use advent_2023::{
+    bitgrid::{low_bits, BitGrid},
+    cycles,
+};
+use std::{env, io::stdin};
+
+// Round rocks and cube-shaped rocks, everything else is empty
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Map {
+    rocks: BitGrid,
+    cubes: BitGrid,
+}
 
 fn print_input(m: &Map) {
-    for line in m.iter() {
-        let s: String = line.iter().collect();
+    for i in 0..m.rocks.height() {
+        let s: String = (0..m.rocks.width())
+            .map(|j| match (m.rocks.get(i, j), m.cubes.get(i, j)) {
+                (true, _) => 'O',
+                (_, true) => '#',
+                _ => '.',
+            })
+            .collect();
         println!("{s}");
     }
 }
 
 fn read_input() -> Map {
-    let mut result = Vec::new();
+    let mut lines = Vec::new();
     for line in stdin().lines().map(|l| l.unwrap()) {
-        result.push(line.chars().collect());
+        lines.push(line.chars().collect::<Vec<_>>());
     }
 
-    result
+    Map {
+        rocks: BitGrid::from_chars(&lines, 'O'),
+        cubes: BitGrid::from_chars(&lines, '#'),
+    }
 }
 
-fn tilt_north(map: &Map) -> Map {
-    let mut res = map.clone();
+// Rolls all rocks in a line of length `len` as far as they go towards bit 0 (or the top bit if `!to_low`), stopping
+// at walls. Each stretch between two walls just ends up with as many rocks as it had, packed against one end
+fn slide(rocks: u128, walls: u128, len: usize, to_low: bool) -> u128 {
+    let mut result = 0;
+    let mut start = 0;
 
-    let (n, m) = (res.len(), res[0].len());
+    while start < len {
+        let end = ((walls >> start).trailing_zeros() as usize + start).min(len);
+        let segment = low_bits(end - start) << start;
+        let count = (rocks & segment).count_ones() as usize;
 
-    for j in 0..m {
-        for i in 0..n {
-            if res[i][j] == 'O' {
-                let mut k = i as i32 - 1;
-
-                while k >= 0 && k < (n as i32) && res[k as usize][j] == '.' {
-                    k -= 1;
-                }
-                if k + 1 != i as i32 {
-                    res[(k + 1) as usize][j] = 'O';
-                    res[i][j] = '.';
-                }
-            }
+        if count > 0 {
+            result |= low_bits(count) << if to_low { start } else { end - count };
         }
+        start = end + 1;
     }
 
-    res
+    result
 }
 
-// Much copy-paste 🐕
-fn tilt_south(map: &Map) -> Map {
-    let mut res = map.clone();
-
-    let (n, m) = (res.len(), res[0].len());
-
-    for j in 0..m {
-        for i in (0..n).rev() {
-            if res[i][j] == 'O' {
-                let mut k = i as i32 + 1;
-
-                while k >= 0 && k < (n as i32) && res[k as usize][j] == '.' {
-                    k += 1;
-                }
-                if k - 1 != i as i32 {
-                    res[(k - 1) as usize][j] = 'O';
-                    res[i][j] = '.';
-                }
-            }
-        }
-    }
-
-    res
+#[derive(Clone, Copy)]
+enum Dir {
+    North,
+    South,
+    West,
+    East,
 }
 
-fn tilt_west(map: &Map) -> Map {
-    let mut res = map.clone();
-
-    let (n, m) = (res.len(), res[0].len());
-
-    for i in 0..n {
-        for j in 0..m {
-            if res[i][j] == 'O' {
-                let mut k = j as i32 - 1;
-
-                while k >= 0 && k < (m as i32) && res[i][k as usize] == '.' {
-                    k -= 1;
-                }
-                if k + 1 != j as i32 {
-                    res[i][(k + 1) as usize] = 'O';
-                    res[i][j] = '.';
-                }
-            }
-        }
+// North and south move rocks along the columns, west and east along the rows. North and west roll towards index 0
+fn tilt(map: &Map, dir: Dir) -> Map {
+    let (rocks, cubes, len) = match dir {
+        Dir::North | Dir::South => (map.rocks.cols(), map.cubes.cols(), map.rocks.height()),
+        Dir::West | Dir::East => (map.rocks.rows(), map.cubes.rows(), map.rocks.width()),
+    };
+    let to_low = matches!(dir, Dir::North | Dir::West);
+    let lines = rocks
+        .iter()
+        .zip(cubes)
+        .map(|(rocks, cubes)| slide(*rocks, *cubes, len, to_low))
+        .collect();
+
+    let rocks = match dir {
+        Dir::North | Dir::South => BitGrid::from_cols(lines, len),
+        Dir::West | Dir::East => BitGrid::from_rows(lines, len),
+    };
+    Map {
+        rocks,
+        cubes: map.cubes.clone(),
     }
-
-    res
 }
 
-fn tilt_east(map: &Map) -> Map {
-    let mut res = map.clone();
+fn spin_cycle(map: &Map) -> Map {
+    [Dir::North, Dir::West, Dir::South, Dir::East]
+        .iter()
+        .fold(map.clone(), |current, dir| tilt(&current, *dir))
+}
 
-    let (n, m) = (res.len(), res[0].len());
+struct Spin {
+    start: Map,
+    prefix: usize,
+    period: usize,
+}
 
-    for i in 0..n {
-        for j in (0..m).rev() {
-            if res[i][j] == 'O' {
-                let mut k = j as i32 + 1;
+impl Spin {
+    // The platform after any number of spin cycles, running at most `prefix + period` of them
+    fn after(&self, rounds: usize) -> Map {
+        let rounds = if rounds < self.prefix {
+            rounds
+        } else {
+            self.prefix + (rounds - self.prefix) % self.period
+        };
 
-                while k >= 0 && k < (m as i32) && res[i][k as usize] == '.' {
-                    k += 1;
-                }
-                if k - 1 != j as i32 {
-                    res[i][(k - 1) as usize] = 'O';
-                    res[i][j] = '.';
-                }
-            }
-        }
+        (0..rounds).fold(self.start.clone(), |current, _| spin_cycle(&current))
     }
-
-    res
 }
 
-fn simulate(map: &Map, rounds: i32) -> Map {
-    let mut round = 1;
-    let mut current = map.clone();
-    let mut seen: HashMap<Map, i32> = HashMap::new();
+fn simulate(map: &Map) -> Spin {
+    // Brent's method doesn't need to keep every platform it has seen around
+    let (prefix, period) = cycles::brent(map, spin_cycle);
 
-    while round <= rounds {
-        current = tilt_east(&tilt_south(&tilt_west(&tilt_north(&current))));
-
-        if let Some(&rnd) = seen.get(&current) {
-            let cycle_len = round - rnd;
-            let remaining = rounds - round;
-            let cycles = remaining / cycle_len;
-
-            round += cycles * cycle_len;
-        } else {
-            seen.insert(current.clone(), round);
-        }
-        round += 1;
+    Spin {
+        start: map.clone(),
+        prefix,
+        period,
     }
-
-    current
 }
 
 fn score(map: &Map) -> i32 {
-    let n = map.len();
+    let n = map.rocks.height();
     let mut res = 0;
 
-    for (i, line) in map.iter().enumerate() {
-        for c in line.iter() {
-            if *c == 'O' {
-                res += n - i;
-            }
-        }
+    for (i, line) in map.rocks.rows().iter().enumerate() {
+        res += line.count_ones() as usize * (n - i);
     }
 
     res as i32
@@ -155,20 +136,32 @@ fn score(map: &Map) -> i32 {
 
 fn part_one() {
     let input = read_input();
-    let result = score(&tilt_north(&input));
+    let result = score(&tilt(&input, Dir::North));
 
     println!("{result}");
 }
 
-fn part_two() {
+fn part_two(rounds: usize) {
     let input = read_input();
-    let rounds = 1_000_000_000;
 
-    let final_map = simulate(&input, rounds);
-    // let final_map = tilt_north(&input);
-    println!("{}", score(&final_map));
+    let spin = simulate(&input);
+    eprintln!(
+        "The platform repeats every {} spin cycles after the first {}",
+        spin.period, spin.prefix
+    );
+    println!("{}", score(&spin.after(rounds)));
 }
 
 fn main() {
-    part_two();
+    // `--rounds <n>` gives the load after n spin cycles instead of a billion
+    let args = env::args().collect::<Vec<_>>();
+    let rounds = match args.iter().position(|arg| arg == "--rounds") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .expect("--rounds needs a number"),
+        None => 1_000_000_000,
+    };
+
+    part_two(rounds);
 }
@@ -1,23 +1,47 @@
-use std::io::stdin;
+use std::{env, fmt, io::stdin};
 
-fn hash(s: &str) -> i32 {
+fn part_one() {
     let mut res = 0;
-    for c in s.chars() {
-        res = (res + (c as i32)) * 17 % 256;
+    for line in stdin().lines().map(|l| l.unwrap()) {
+        let parts = line.split(",");
+        for part in parts {
+            res += HashFn::Holiday.bucket(part, 256);
+        }
     }
 
-    res
+    println!("{res}");
 }
 
-fn part_one() {
-    for line in stdin().lines().map(|l| l.unwrap()) {
-        let parts = line.split(",");
-        let mut res = 0;
-        for part in parts {
-            res += hash(part);
+#[derive(Clone, Copy, Debug)]
+enum HashFn {
+    // The puzzle's HASH, reducing modulo the number of boxes instead of 256
+    Holiday,
+    Fnv1a,
+    // Just adds up the characters, to see what lots of collisions look like
+    Sum,
+}
+
+impl HashFn {
+    fn parse(name: &str) -> HashFn {
+        match name {
+            "holiday" => HashFn::Holiday,
+            "fnv1a" => HashFn::Fnv1a,
+            "sum" => HashFn::Sum,
+            _ => panic!("unknown hash function {name}, expected holiday, fnv1a or sum"),
         }
+    }
 
-        println!("{res}");
+    fn bucket(&self, s: &str, buckets: usize) -> usize {
+        match self {
+            HashFn::Holiday => s.bytes().fold(0, |res, b| (res + b as usize) * 17 % buckets),
+            HashFn::Fnv1a => {
+                let hash = s.bytes().fold(0xcbf29ce484222325u64, |res, b| {
+                    (res ^ b as u64).wrapping_mul(0x100000001b3)
+                });
+                (hash % buckets as u64) as usize
+            }
+            HashFn::Sum => s.bytes().map(|b| b as usize).sum::<usize>() % buckets,
+        }
     }
 }
 
@@ -32,60 +56,144 @@ struct Box {
     lenses: Vec<Lens>,
 }
 
-fn simulate(instructions: &Vec<&str>) -> Vec<Box> {
-    let mut boxes = vec![Box { lenses: Vec::new() }; 256];
+enum Op {
+    Remove(String),
+    Insert(String, i32),
+}
 
-    for &instr in instructions {
-        if instr.contains("-") {
-            let label = instr.split("-").next().unwrap().to_string();
-            let box_num = hash(&label);
+impl Op {
+    fn parse(instr: &str) -> Op {
+        if let Some(label) = instr.strip_suffix("-") {
+            Op::Remove(label.to_string())
+        } else if let Some((label, length)) = instr.split_once("=") {
+            Op::Insert(label.to_string(), length.parse::<i32>().unwrap())
+        } else {
+            panic!("{instr} is neither a removal nor an insertion")
+        }
+    }
+}
 
-            let b = &mut boxes[box_num as usize];
-            if let Some(idx) = b.lenses.iter().position(|lens| lens.label == label) {
-                b.lenses.remove(idx);
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Remove(label) => write!(f, "{label}-"),
+            Op::Insert(label, length) => write!(f, "{label}={length}"),
+        }
+    }
+}
+
+struct LensLibrary {
+    boxes: Vec<Box>,
+    hash: HashFn,
+}
+
+impl LensLibrary {
+    fn new(buckets: usize, hash: HashFn) -> LensLibrary {
+        LensLibrary {
+            boxes: vec![Box { lenses: Vec::new() }; buckets],
+            hash,
+        }
+    }
+
+    fn apply(&mut self, op: &Op) {
+        match op {
+            Op::Remove(label) => {
+                let b = self.box_for(label);
+                if let Some(idx) = b.lenses.iter().position(|lens| lens.label == *label) {
+                    b.lenses.remove(idx);
+                }
             }
-        } else if instr.contains("=") {
-            let parts = instr.split("=").collect::<Vec<_>>();
-            let label = parts[0].to_string();
-            let box_num = hash(&label);
-            let length = parts[1].parse::<i32>().unwrap();
-
-            let b = &mut boxes[box_num as usize];
-            if let Some(idx) = b.lenses.iter().position(|lens| lens.label == label) {
-                b.lenses[idx].length = length;
-            } else {
-                b.lenses.push(Lens {
-                    label: label,
-                    length: length,
-                });
+            Op::Insert(label, length) => {
+                let b = self.box_for(label);
+                if let Some(idx) = b.lenses.iter().position(|lens| lens.label == *label) {
+                    b.lenses[idx].length = *length;
+                } else {
+                    b.lenses.push(Lens {
+                        label: label.clone(),
+                        length: *length,
+                    });
+                }
             }
         }
     }
 
-    boxes
-}
+    fn box_for(&mut self, label: &str) -> &mut Box {
+        let box_num = self.hash.bucket(label, self.boxes.len());
+        &mut self.boxes[box_num]
+    }
 
-fn total_power(boxes: &Vec<Box>) -> i32 {
-    let mut result = 0;
-    for (i, b) in boxes.iter().enumerate() {
-        for (j, lens) in b.lenses.iter().enumerate() {
-            result += ((i + 1) as i32) * ((j + 1) as i32) * lens.length;
+    fn total_power(&self) -> i32 {
+        let mut result = 0;
+        for (i, b) in self.boxes.iter().enumerate() {
+            for (j, lens) in b.lenses.iter().enumerate() {
+                result += ((i + 1) as i32) * ((j + 1) as i32) * lens.length;
+            }
         }
+
+        result
     }
 
-    result
+    // How many boxes have lenses in them, and how many lenses the fullest one has
+    fn occupancy(&self) -> (usize, usize) {
+        let used = self.boxes.iter().filter(|b| !b.lenses.is_empty()).count();
+        let fullest = self.boxes.iter().map(|b| b.lenses.len()).max().unwrap_or(0);
+        (used, fullest)
+    }
+}
+
+// The non-empty boxes, the way the puzzle text shows them
+impl fmt::Display for LensLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.boxes.iter().enumerate() {
+            if !b.lenses.is_empty() {
+                let lenses = b
+                    .lenses
+                    .iter()
+                    .map(|lens| format!("[{} {}]", lens.label, lens.length))
+                    .collect::<Vec<_>>();
+                writeln!(f, "Box {i}: {}", lenses.join(" "))?;
+            }
+        }
+        Ok(())
+    }
 }
 
-fn part_two() {
+fn part_two(buckets: usize, hash: HashFn, trace: bool, stats: bool) {
+    let mut library = LensLibrary::new(buckets, hash);
     for line in stdin().lines().map(|l| l.unwrap()) {
-        let instructions = line.split(",").collect::<Vec<_>>();
+        for instr in line.split(",").filter(|instr| !instr.is_empty()) {
+            let op = Op::parse(instr);
+            library.apply(&op);
+
+            if trace {
+                println!("After \"{op}\":\n{library}");
+            }
+        }
+    }
 
-        let final_state = simulate(&instructions);
-        let result = total_power(&final_state);
-        println!("{result}");
+    if stats {
+        let (used, fullest) = library.occupancy();
+        println!("{used} of {buckets} boxes used, at most {fullest} lenses in one box");
     }
+    println!("{}", library.total_power());
 }
 
 fn main() {
-    part_two();
+    // `--trace` prints the boxes after every step, `--stats` how the lenses are spread over them. `--buckets <n>` and
+    // `--hash holiday|fnv1a|sum` change how many boxes there are and how labels are assigned to them
+    let args = env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("{flag} needs a value")))
+    };
+
+    let buckets = value("--buckets").map_or(256, |n| n.parse().expect("--buckets needs a number"));
+    let hash = value("--hash").map_or(HashFn::Holiday, |name| HashFn::parse(name));
+    part_two(
+        buckets,
+        hash,
+        args.iter().any(|arg| arg == "--trace"),
+        args.iter().any(|arg| arg == "--stats"),
+    );
 }
@@ -1,10 +1,10 @@
 use std::{
-    cmp,
     collections::{HashMap, VecDeque},
     io::stdin,
     vec,
 };
 
+use advent_2023::cycles::Schedule;
 use scanf::sscanf;
 
 #[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
@@ -172,22 +172,6 @@ fn part_one() {
     println!("{result}");
 }
 
-fn gcd(i1: i64, i2: i64) -> i64 {
-    let (mut d1, mut d2) = (cmp::max(i1, i2), cmp::min(i1, i2));
-
-    while d2 != 0 {
-        let rem = d1 % d2;
-        d1 = d2;
-        d2 = rem;
-    }
-
-    d1
-}
-
-fn lcm(i1: i64, i2: i64) -> i64 {
-    (i1 / gcd(i1, i2)) * i2
-}
-
 fn simulate_2(start: &Input) -> i64 {
     let (mapping, modules, incoming, outgoing) = start;
     let mut current = modules.clone();
@@ -204,7 +188,7 @@ fn simulate_2(start: &Input) -> i64 {
     // Which is connected only to several other conjunctions
     // We need to figure out when these conjunctions emit "High" signals
     // It seems that they're independent and do that on a certain period
-    // So the answer is the LCM of all those periods
+    // So the answer is the first press that's in all of those periodic schedules
 
     let mut emitted_high = vec![Vec::new(); modules.len()];
     let conjunctions_of_interest = &incoming[destination_idx];
@@ -252,15 +236,15 @@ fn simulate_2(start: &Input) -> i64 {
                             .iter()
                             .all(|idx| emitted_high[*idx].len() > 1)
                         {
-                            let mut result = 1i64;
-                            for idx in conjunctions_of_interest {
-                                let sent_times = &emitted_high[*idx];
-                                assert!(sent_times[1] - sent_times[0] == sent_times[0]);
-
-                                result = lcm(result, sent_times[0]);
-                            }
-
-                            return result;
+                            return conjunctions_of_interest
+                                .iter()
+                                .map(|idx| {
+                                    let sent_times = &emitted_high[*idx];
+                                    Schedule::periodic(sent_times[0], sent_times[1] - sent_times[0])
+                                })
+                                .reduce(|a, b| a.combine(&b))
+                                .and_then(|schedule| schedule.first())
+                                .expect("the conjunctions never all send High on the same press");
                         }
                     }
 
@@ -1,5 +1,6 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    env, fs,
     io::stdin,
 };
 
@@ -212,53 +213,407 @@ fn reachable_count_2_recurrence_relation(start: Pos, max_dist: i32, map: &Vec<Ve
     count
 }
 
-fn reachable_count_2_fill(start: Pos, max_dist: i32, map: &Vec<Vec<char>>) -> i64 {
+// Distances from `start` to every cell of a block of (2 * radius + 1)^2 tiles centred on the original one,
+// indexed by [tile y][tile x][y][x] with tiles numbered from 0. Paths can't leave the block, so distances
+// close to its border may be too large
+fn block_distances(start: Pos, radius: i32, map: &Vec<Vec<char>>) -> Vec<Vec<Vec<Vec<Option<i64>>>>> {
     let (h, w) = (map.len() as i32, map[0].len() as i32);
-    assert!(h == w);
+    let tiles = 2 * radius + 1;
+
+    let mut dist = vec![vec![vec![vec![None; w as usize]; h as usize]; tiles as usize]; tiles as usize];
+    let at = |p: Pos| {
+        (
+            (p.y.div_euclid(h) + radius) as usize,
+            (p.x.div_euclid(w) + radius) as usize,
+            p.y.rem_euclid(h) as usize,
+            p.x.rem_euclid(w) as usize,
+        )
+    };
+    let inside = |p: Pos| {
+        let (ty, tx) = (p.y.div_euclid(h), p.x.div_euclid(w));
+        ty.abs() <= radius && tx.abs() <= radius
+    };
+
+    let mut q = VecDeque::new();
+    q.push_back((start, 0));
+    let (ty, tx, y, x) = at(start);
+    dist[ty][tx][y][x] = Some(0);
+
+    while let Some((pos, d)) = q.pop_front() {
+        for n in neighbours_2(pos, map) {
+            if !inside(n) {
+                continue;
+            }
+            let (ty, tx, y, x) = at(n);
+            if dist[ty][tx][y][x].is_none() {
+                dist[ty][tx][y][x] = Some(d + 1);
+                q.push_back((n, d + 1));
+            }
+        }
+    }
+
+    dist
+}
+
+// Number of k >= 0 with d + k * step <= steps, and d + k * step of the same parity as steps
+fn count_line(d: i64, step: i64, steps: i64) -> i64 {
+    if d > steps {
+        return 0;
+    }
+    let max_k = (steps - d) / step;
+    if step % 2 == 0 {
+        if (steps - d) % 2 == 0 {
+            max_k + 1
+        } else {
+            0
+        }
+    } else if (steps - d) % 2 == 0 {
+        max_k / 2 + 1
+    } else {
+        (max_k + 1) / 2
+    }
+}
+
+// Sum of floor((a * i + b) / m) for i in 0..n, with a, b >= 0 and m > 0
+fn floor_sum(n: i64, m: i64, a: i64, b: i64) -> i128 {
+    let (mut n, mut m, mut a, mut b) = (n as i128, m as i128, a as i128, b as i128);
+    let mut result = 0;
+    loop {
+        if a >= m {
+            result += n * (n - 1) / 2 * (a / m);
+            a %= m;
+        }
+        if b >= m {
+            result += n * (b / m);
+            b %= m;
+        }
+        let y_max = a * n + b;
+        if y_max < m {
+            return result;
+        }
+        (n, b, m, a) = (y_max / m, y_max % m, a, m);
+    }
+}
+
+// Number of i, j >= 0 with d + i * step_x + j * step_y <= steps and of the same parity as steps
+fn count_quadrant(d: i64, step_x: i64, step_y: i64, steps: i64) -> i64 {
+    let mut result = 0;
+    // Split i and j by parity - for i = 2a + ri, j = 2b + rj the parity of the total no longer depends on a and b
+    for ri in 0..2 {
+        for rj in 0..2 {
+            let rest = steps - d - ri * step_x - rj * step_y;
+            if rest < 0 || rest % 2 != 0 {
+                continue;
+            }
+            // Number of a, b >= 0 with a * 2 * step_x + b * 2 * step_y <= rest
+            let (sx, sy) = (2 * step_x, 2 * step_y);
+            let max_a = rest / sx;
+            result += floor_sum(max_a + 1, sy, sx, rest - sx * max_a + sy) as i64;
+        }
+    }
+    result
+}
+
+// How much further every cell of `outer` is than the same cell of `inner`, if that's the same for all cells.
+// Ok(None) if neither tile has any reachable cells
+fn tile_offset(inner: &[Vec<Option<i64>>], outer: &[Vec<Option<i64>>]) -> Result<Option<i64>, ()> {
+    let mut offset = None;
+    for (inner_row, outer_row) in inner.iter().zip(outer) {
+        for (&a, &b) in inner_row.iter().zip(outer_row) {
+            match (a, b) {
+                (None, None) => {}
+                (Some(a), Some(b)) if offset.is_none() || offset == Some(b - a) => offset = Some(b - a),
+                _ => return Err(()),
+            }
+        }
+    }
+    Ok(offset)
+}
+
+// Steps added per tile when moving outwards from the ring of tiles `r` away from the original one, for each
+// direction including the diagonals. None if that isn't the same for all cells yet
+fn outward_steps(dist: &[Vec<Vec<Vec<Option<i64>>>>], r: i32) -> Option<HashMap<(i32, i32), i64>> {
+    let radius = (dist.len() / 2) as i32;
+    let tile = |tx: i32, ty: i32| &dist[(ty + radius) as usize][(tx + radius) as usize];
+
+    let mut step: HashMap<(i32, i32), i64> = HashMap::new();
+    for ty in -r..=r {
+        for tx in -r..=r {
+            let dx = if tx.abs() == r { tx.signum() } else { 0 };
+            let dy = if ty.abs() == r { ty.signum() } else { 0 };
+            for (sx, sy) in [(dx, 0), (0, dy), (dx, dy)] {
+                if (sx, sy) == (0, 0) {
+                    continue;
+                }
+                if let Some(offset) = tile_offset(tile(tx, ty), tile(tx + sx, ty + sy)).ok()? {
+                    if *step.entry((sx, sy)).or_insert(offset) != offset {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    Some(step)
+}
+
+// The next layer of a BFS on the infinitely tiled map. A neighbour of the current layer is either in one of the
+// last two layers or in the next one, so that's all that needs to be kept around
+fn next_layer(previous: &HashSet<Pos>, current: &HashSet<Pos>, map: &Vec<Vec<char>>) -> HashSet<Pos> {
+    let mut next = HashSet::new();
+    for &pos in current {
+        for n in neighbours_2(pos, map) {
+            if !previous.contains(&n) && !current.contains(&n) {
+                next.insert(n);
+            }
+        }
+    }
+
+    next
+}
+
+// Counts cells reachable in exactly `steps` steps from how many cells there are at each distance. Those counts
+// eventually grow by the same amount every `period` distances, separately for each distance mod `period`, so once
+// that holds for the second half of the distances computed so far, everything past them follows by extrapolation.
+// Slower than `reachable_count_tiled`, but it doesn't care how the distances are laid out over the tiles
+fn reachable_count_periodic(start: Pos, steps: i64, map: &Vec<Vec<char>>) -> i64 {
+    let (h, w) = (map.len(), map[0].len());
+    let same_parity = |d: usize| d as i64 <= steps && (steps - d as i64) % 2 == 0;
+
+    let (mut previous, mut current) = (HashSet::new(), HashSet::from([start]));
+    let mut sizes = vec![1];
+    let mut max_d = 8 * (h + w);
+    let (tail, period) = loop {
+        while sizes.len() <= max_d.min(steps as usize) {
+            let next = next_layer(&previous, &current, map);
+            sizes.push(next.len() as i64);
+            (previous, current) = (current, next);
+        }
+        if steps as usize <= max_d {
+            return (0..sizes.len()).filter(|&d| same_parity(d)).map(|d| sizes[d]).sum();
+        }
+
+        let tail = max_d / 2;
+        let settled = |period: usize| {
+            (tail..=max_d - 2 * period)
+                .all(|d| sizes[d + 2 * period] - 2 * sizes[d + period] + sizes[d] == 0)
+        };
+        // Only even periods, which keep the parity the same within each distance mod period (twice an odd period is
+        // a period too). Up to an eighth of the distances, so every distance mod period is checked at least twice
+        // and tail + 2 * period stays within the ones computed
+        if let Some(period) = (2..=max_d / 8).step_by(2).find(|&period| settled(period)) {
+            break (tail, period);
+        }
+        max_d *= 2;
+    };
+
+    let mut result = (0..tail).filter(|&d| same_parity(d)).map(|d| sizes[d]).sum::<i64>();
+    for d in (tail..tail + period).filter(|&d| same_parity(d)) {
+        // Distances d, d + period, d + 2 * period, ... up to `steps`, with sizes going up by `growth` each time
+        let k = (steps - d as i64) / period as i64;
+        let growth = sizes[d + period] - sizes[d];
+        result += (k + 1) * sizes[d] + growth * k * (k + 1) / 2;
+    }
+
+    result
+}
+
+// Counts cells reachable in exactly `steps` steps on the infinitely tiled map, for any map size, start and step
+// count. Distances are computed over a block of tiles around the start. Far enough out, moving one tile further
+// in a given direction adds the same number of steps to every cell - that's checked on the outermost ring of the
+// block (growing it until it holds), and the tiles beyond the block are then counted in closed form. On some
+// gardens that never holds, as tiles near the diagonals get further by a different amount than the ones on the
+// axes, and then it's left to `reachable_count_periodic`
+fn reachable_count_tiled(start: Pos, steps: i64, map: &Vec<Vec<char>>) -> i64 {
+    let mut r = 2;
+    let (dist, step) = loop {
+        if r > 8 {
+            return reachable_count_periodic(start, steps, map);
+        }
+
+        // One spare ring on the outside, as its distances are skewed by the block's border
+        let dist = block_distances(start, r + 2, map);
+        if let Some(step) = outward_steps(&dist, r) {
+            break (dist, step);
+        }
+        r *= 2;
+    };
+
+    let mut result = 0;
+    for ty in -r..=r {
+        for tx in -r..=r {
+            let (dx, dy) = (tx.signum(), ty.signum());
+            let tile = &dist[(ty + r + 2) as usize][(tx + r + 2) as usize];
+            for d in tile.iter().flatten().flatten().copied() {
+                result += match (tx.abs() == r, ty.abs() == r) {
+                    (false, false) => (d <= steps && (steps - d) % 2 == 0) as i64,
+                    (true, false) => count_line(d, step[&(dx, 0)], steps),
+                    (false, true) => count_line(d, step[&(0, dy)], steps),
+                    (true, true) => {
+                        // A diagonal step can be cheaper than a step along each axis, so the tile i across and
+                        // j up is min(i, j) diagonal steps plus the rest along one of the axes away. That splits
+                        // the quadrant into the part below the diagonal and the part above it
+                        let (step_x, step_y, diagonal) = (step[&(dx, 0)], step[&(0, dy)], step[&(dx, dy)]);
+                        count_quadrant(d, step_x, diagonal, steps)
+                            + count_quadrant(d + step_y, step_y, diagonal, steps)
+                    }
+                };
+            }
+        }
+    }
+
+    result
+}
+
+fn verify_against_naive(start: Pos, max_steps: i32, map: &Vec<Vec<char>>) {
+    for steps in 0..=max_steps {
+        let naive = reachable_cells_naive(start, steps, map).len() as i64;
+        let tiled = reachable_count_tiled(start, steps as i64, map);
+        assert_eq!(naive, tiled, "mismatch after {steps} steps");
+    }
+    println!("Tiled count matches the naive one for up to {max_steps} steps");
+}
+
+// What a tile is after `steps` steps, for a square garden with the start in the middle: whole tiles of either
+// parity, the corners at the tips of the diamond, or the large and small tiles along its edges
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TileKind {
+    Odd,
+    Even,
+    Corner,
+    Large,
+    Small,
+    Outside,
+}
+
+fn tile_kind(tx: i32, ty: i32, steps: i32, start: Pos, map: &[Vec<char>]) -> TileKind {
+    let (h, w) = (map.len() as i32, map[0].len() as i32);
+    let m = tx.abs() + ty.abs();
+
+    // Cells of this tile are tx * w + ty * h further from the start than the same cells of the original tile, so
+    // whether the plots reached in it are the ones at odd or even distances from the start's copy depends on that
+    // and on the step count
+    let parity = if (steps + tx * w + ty * h).rem_euclid(2) == 1 {
+        TileKind::Odd
+    } else {
+        TileKind::Even
+    };
+
+    // Only square maps with the start in the middle are split up like that, otherwise just show the parity
+    if h != w || start.x != w / 2 || start.y != h / 2 {
+        return parity;
+    }
     let n = h;
+    if steps < n / 2 {
+        return if m == 0 { parity } else { TileKind::Outside };
+    }
 
-    assert!(max_dist % n == n / 2);
-    assert!(start.x == n / 2);
-    assert!(start.y == n / 2);
-    let odd = reachable_count(start, 2 * n + 1, map) as i64;
-    let even = reachable_count(start, 2 * n, map) as i64;
-
-    let grid_half_diag = ((max_dist - (n / 2)) / n) as i64;
-    let odd_cnt = ((grid_half_diag / 2) * 2 - 1).pow(2);
-    let even_cnt = ((grid_half_diag / 2) * 2).pow(2);
-
-    let corner_top = reachable_count(Pos { x: n / 2, y: n - 1 }, n - 1, map) as i64;
-    let corner_right = reachable_count(Pos { x: 0, y: n / 2 }, n - 1, map) as i64;
-    let corner_bottom = reachable_count(Pos { x: n / 2, y: 0 }, n - 1, map) as i64;
-    let corner_left = reachable_count(Pos { x: n - 1, y: n / 2 }, n - 1, map) as i64;
-
-    let small_top_right = reachable_count(Pos { x: 0, y: n - 1 }, n / 2 - 1, map) as i64;
-    let small_top_left = reachable_count(Pos { x: n - 1, y: n - 1 }, n / 2 - 1, map) as i64;
-    let small_bottom_left = reachable_count(Pos { x: n - 1, y: 0 }, n / 2 - 1, map) as i64;
-    let small_bottom_right = reachable_count(Pos { x: 0, y: 0 }, n / 2 - 1, map) as i64;
-
-    let large_top_right = reachable_count(Pos { x: 0, y: n - 1 }, n + n / 2 - 1, map) as i64;
-    let large_top_left = reachable_count(Pos { x: n - 1, y: n - 1 }, n + n / 2 - 1, map) as i64;
-    let large_bottom_left = reachable_count(Pos { x: n - 1, y: 0 }, n + n / 2 - 1, map) as i64;
-    let large_bottom_right = reachable_count(Pos { x: 0, y: 0 }, n + n / 2 - 1, map) as i64;
-
-    return odd_cnt * odd
-        + even_cnt * even
-        + (corner_top + corner_right + corner_bottom + corner_left)
-        + grid_half_diag
-            * (small_top_left + small_top_right + small_bottom_left + small_bottom_right)
-        + (grid_half_diag - 1)
-            * (large_top_left + large_top_right + large_bottom_left + large_bottom_right);
+    let grid_half_diag = (steps - n / 2) / n;
+    let on_axis = tx == 0 || ty == 0;
+    match m {
+        _ if m < grid_half_diag => parity,
+        _ if m == grid_half_diag && on_axis => TileKind::Corner,
+        _ if m == grid_half_diag => TileKind::Large,
+        _ if m == grid_half_diag + 1 && !on_axis => TileKind::Small,
+        _ => TileKind::Outside,
+    }
+}
+
+fn tile_colour(kind: TileKind) -> [u8; 3] {
+    match kind {
+        TileKind::Odd => [70, 130, 230],
+        TileKind::Even => [60, 190, 120],
+        TileKind::Corner => [230, 80, 70],
+        TileKind::Large => [240, 170, 50],
+        TileKind::Small => [190, 90, 210],
+        TileKind::Outside => [150, 150, 150],
+    }
 }
 
-fn part_two() {
+// Writes one PPM image per step from 0 to `max_steps` into `dir`, showing the tiled garden with the plots
+// reachable after exactly that many steps lit up. Tiles are coloured by their `tile_kind`
+fn write_frames(dir: &str, start: Pos, max_steps: i32, map: &Vec<Vec<char>>) {
+    const SCALE: usize = 4;
+    let (h, w) = (map.len() as i32, map[0].len() as i32);
+
+    // Nothing further than `max_steps` away can be reached, so these distances are exact
+    let radius = max_steps / h.min(w) + 1;
+    let dist = block_distances(start, radius, map);
+    let tiles = 2 * radius + 1;
+    let (width, height) = ((tiles * w) as usize * SCALE, (tiles * h) as usize * SCALE);
+
+    fs::create_dir_all(dir).expect("can't create frame directory");
+    for steps in 0..=max_steps {
+        let mut pixels = vec![0u8; width * height * 3];
+        for (ty, tile_row) in dist.iter().enumerate() {
+            for (tx, tile) in tile_row.iter().enumerate() {
+                let kind = tile_kind(tx as i32 - radius, ty as i32 - radius, steps, start, map);
+                let colour = tile_colour(kind);
+
+                for (y, row) in tile.iter().enumerate() {
+                    for (x, d) in row.iter().enumerate() {
+                        let reached = d.is_some_and(|d| d <= steps as i64 && (steps as i64 - d) % 2 == 0);
+                        let rgb = if map[y][x] == '#' {
+                            [20, 20, 20]
+                        } else if reached {
+                            colour
+                        } else {
+                            colour.map(|c| c / 4 + 170)
+                        };
+
+                        let (px, py) = ((tx * w as usize + x) * SCALE, (ty * h as usize + y) * SCALE);
+                        for dy in 0..SCALE {
+                            for dx in 0..SCALE {
+                                let i = ((py + dy) * width + px + dx) * 3;
+                                pixels[i..i + 3].copy_from_slice(&rgb);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut image = format!("P6\n{width} {height}\n255\n").into_bytes();
+        image.extend(pixels);
+        fs::write(format!("{dir}/{steps:05}.ppm"), image).expect("can't write frame");
+    }
+}
+
+fn part_two(steps: i64, verify_steps: Option<i32>) {
     let (map, start) = read_input();
 
-    let steps = 26501365;
-    let result = reachable_count_2_fill(start, steps, &map);
+    if let Some(max_steps) = verify_steps {
+        verify_against_naive(start, max_steps, &map);
+    }
+
+    let result = reachable_count_tiled(start, steps, &map);
     println!("{result}");
 }
 
 fn main() {
-    part_two();
+    // `--steps <n>` to count after something other than the puzzle's step count,
+    // `--verify <n>` to first check the result against the naive BFS for every step count up to n (21_example_2.input
+    // and 21_example_3.input are gardens that need `reachable_count_periodic`, e.g. with `--steps 100 --verify 80`
+    // and `--steps 5000 --verify 600`, the latter only settling after a few hundred steps),
+    // `--frames <dir>` to draw every step up to `--steps` (which has to be given then) into dir instead
+    let args = env::args().collect::<Vec<_>>();
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .map(|i| args.get(i + 1).expect("missing argument value").parse().unwrap())
+    };
+
+    let steps = arg_value("--steps");
+    if let Some(i) = args.iter().position(|arg| arg == "--frames") {
+        let dir = args.get(i + 1).expect("--frames needs a directory");
+        // One frame per step, so the puzzle's step count would be far too many
+        let steps = steps.expect("--frames needs --steps");
+        let (map, start) = read_input();
+        write_frames(dir, start, steps as i32, &map);
+        return;
+    }
+    let steps = steps.unwrap_or(26501365);
+
+    let verify_steps = arg_value("--verify").map(|n| n as i32);
+    part_two(steps, verify_steps);
 }
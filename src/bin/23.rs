@@ -1,7 +1,8 @@
 use std::{
     cmp,
-    collections::{HashMap, HashSet, VecDeque},
-    io::{empty, stdin},
+    collections::{HashMap, HashSet},
+    env, fs,
+    io::stdin,
     vec,
 };
 
@@ -35,65 +36,21 @@ fn get_dir(arrow: char) -> Dir {
     }
 }
 
-fn longest_path_len(maze: &Vec<Vec<char>>, start: Pos, end: Pos) -> i32 {
-    let (n, m) = (maze.len() as i32, maze[0].len() as i32);
-    let mut q: VecDeque<(i32, Pos, Option<Dir>, HashSet<Pos>)> = VecDeque::new();
-    q.push_back((0, start, None, HashSet::new()));
-
-    let mut result = 0;
-    while !q.is_empty() {
-        let (dist, pos, next_dir, mut visited) = q.pop_front().unwrap();
-        visited.insert(pos);
-
-        if pos == end {
-            if dist > result {
-                result = dist;
-            }
-            continue;
-        }
-
-        let dirs = if next_dir.is_some() {
-            vec![next_dir.unwrap()]
-        } else {
-            vec![UP, DOWN, LEFT, RIGHT]
-        };
-
-        let mut possible_next = Vec::new();
-        for dir in dirs {
-            let next = Pos {
-                x: pos.x + dir.x,
-                y: pos.y + dir.y,
-            };
-            if next.x >= 0 && next.x < m && next.y >= 0 && next.y < n {
-                let c = maze[next.y as usize][next.x as usize];
-                if c == '.' {
-                    possible_next.push((next, None));
-                } else if "^>v<".contains(c) {
-                    let dir_next = get_dir(c);
-                    possible_next.push((next, Some(dir_next)));
-                }
-            }
-        }
-
-        if possible_next.len() == 1 {
-            let (next_pos, forced_dir) = possible_next[0];
+enum RouteOutput {
+    None,
+    Terminal,
+    Svg(String),
+}
 
-            if !visited.contains(&next_pos) {
-                q.push_back((dist + 1, next_pos, forced_dir, visited));
-            }
-        } else {
-            for (next_pos, forced_dir) in possible_next {
-                if !visited.contains(&next_pos) {
-                    let new_visited = visited.clone();
-                    q.push_back((dist + 1, next_pos, forced_dir, new_visited));
-                }
-            }
-        }
+fn show_route(maze: &[Vec<char>], route: &[Pos], output: &RouteOutput) {
+    match output {
+        RouteOutput::None => {}
+        RouteOutput::Terminal => print!("{}", render_route(maze, route)),
+        RouteOutput::Svg(path) => fs::write(path, route_svg(maze, route)).expect("can't write svg"),
     }
-    result
 }
 
-fn part_one() {
+fn part_one(output: &RouteOutput) {
     let maze = read_input();
     let start = Pos { x: 1, y: 0 };
     let end = Pos {
@@ -103,8 +60,12 @@ fn part_one() {
     assert!(maze[start.y as usize][start.x as usize] == '.');
     assert!(maze[end.y as usize][end.x as usize] == '.');
 
-    let result = longest_path_len(&maze, start, end);
+    let (result, route, acyclic) = longest_path(&maze, start, end);
     println!("{result}");
+    if !acyclic {
+        println!("Slopes don't make the junction graph acyclic, fell back to exhaustive search");
+    }
+    show_route(&maze, &route, output);
 }
 
 fn empty_neighbours(pos: Pos, maze: &Vec<Vec<char>>) -> Vec<Pos> {
@@ -127,10 +88,38 @@ fn empty_neighbours(pos: Pos, maze: &Vec<Vec<char>>) -> Vec<Pos> {
     result
 }
 
+// Walks the corridor starting with the step `from` -> `first` until it hits an intersection or a dead end, returning
+// the cells walked. On slippery maps every slope along the way has to be walked downhill, otherwise the corridor
+// can't be taken in this direction
+fn follow_corridor(maze: &Vec<Vec<char>>, from: Pos, first: Pos, slippery: bool) -> Option<Vec<Pos>> {
+    let (mut prev, mut path) = (from, vec![first]);
+    loop {
+        let pos = *path.last().unwrap();
+        let next = empty_neighbours(pos, maze)
+            .into_iter()
+            .filter(|&p| p != prev)
+            .collect::<Vec<_>>();
+        if next.len() != 1 {
+            return Some(path);
+        }
+
+        let c = maze[pos.y as usize][pos.x as usize];
+        if slippery && "^>v<".contains(c) {
+            let dir = get_dir(c);
+            if next[0] != (Pos { x: pos.x + dir.x, y: pos.y + dir.y }) {
+                return None;
+            }
+        }
+        prev = pos;
+        path.push(next[0]);
+    }
+}
+
 fn find_intersections(
     maze: &Vec<Vec<char>>,
     start: Pos,
     end: Pos,
+    slippery: bool,
 ) -> (Vec<Pos>, Vec<Vec<(usize, i32)>>) {
     let (n, m) = (maze.len() as i32, maze[0].len() as i32);
 
@@ -139,92 +128,324 @@ fn find_intersections(
     for i in 0..n {
         for j in 0..m {
             let pos = Pos { x: j, y: i };
-            if maze[i as usize][j as usize] == '.' && empty_neighbours(pos, maze).len() > 2 {
+            if pos != start
+                && pos != end
+                && maze[i as usize][j as usize] != '#'
+                && empty_neighbours(pos, maze).len() > 2
+            {
                 intersections.push(pos);
             }
         }
     }
     intersections.push(end);
 
-    let mut neighbours = vec![Vec::new(); intersections.len()];
-    for (i, &start) in intersections.iter().enumerate() {
-        let mut q = VecDeque::new();
-        q.push_back((start, 0));
+    let index: HashMap<Pos, usize> = intersections
+        .iter()
+        .enumerate()
+        .map(|(i, &pos)| (pos, i))
+        .collect();
+
+    let mut neighbours: Vec<Vec<(usize, i32)>> = vec![Vec::new(); intersections.len()];
+    for (i, &from) in intersections.iter().enumerate() {
+        // Walk each corridor separately, so that parallel corridors between the same pair of intersections
+        // are all seen - only the longest one can be part of a longest path
+        for first in empty_neighbours(from, maze) {
+            let Some(path) = follow_corridor(maze, from, first, slippery) else {
+                continue;
+            };
+            let dist = path.len() as i32;
+            let Some(&idx) = index.get(path.last().unwrap()) else {
+                continue; // dead end
+            };
+            if idx == i {
+                continue;
+            }
 
-        let mut visited = HashSet::from([start]);
+            match neighbours[i].iter_mut().find(|(other, _)| *other == idx) {
+                Some(edge) => edge.1 = cmp::max(edge.1, dist),
+                None => neighbours[i].push((idx, dist)),
+            }
+        }
+    }
 
-        while !q.is_empty() {
-            let (pos, dist) = q.pop_front().unwrap();
+    (intersections, neighbours)
+}
 
-            if pos != start && intersections.contains(&pos) {
-                let idx = intersections
-                    .iter()
-                    .position(|isect| *isect == pos)
-                    .unwrap();
+struct BitSet {
+    words: Vec<u64>,
+}
 
-                // TODO: Case when there is more than direct path between two intersections is currently unhandled
-                // (didn't occur in example or input)
-                if neighbours[i].iter().any(|&(to, d)| to == idx) {
-                    println!("Possibly snafu");
-                }
+impl BitSet {
+    fn new(size: usize) -> BitSet {
+        BitSet {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+}
+
+struct Search<'a> {
+    neighbours: &'a Vec<Vec<(usize, i32)>>,
+    incoming: Vec<Vec<(usize, i32)>>,
+    end: usize,
+    visited: BitSet,
+    path: Vec<usize>,
+    best: Option<(i32, Vec<usize>)>,
+}
 
-                neighbours[i].push((idx, dist));
+// Upper bound on how much longer a path currently at `node` can get. Every node it enters from here on is reachable
+// through unvisited nodes, and is entered through one of its incoming edges from `node` or another such node, so
+// summing the longest of those edges over the reachable nodes gives a bound. None if `end` can't be reached at all.
+fn remaining_bound(search: &Search, node: usize) -> Option<i32> {
+    let usable = |n: usize| n == node || !search.visited.contains(n);
+
+    let mut reached = BitSet::new(search.neighbours.len());
+    reached.insert(node);
+    let mut stack = vec![node];
+    let mut bound = 0;
+    while let Some(current) = stack.pop() {
+        for &(next, _) in &search.neighbours[current] {
+            if !usable(next) || reached.contains(next) {
                 continue;
             }
-
-            for next in empty_neighbours(pos, maze) {
-                if !visited.contains(&next) {
-                    q.push_back((next, dist + 1));
-                    visited.insert(next);
-                }
+            reached.insert(next);
+
+            let incoming = search.incoming[next]
+                .iter()
+                .filter(|&&(from, _)| usable(from))
+                .collect::<Vec<_>>();
+            // Apart from `end`, the path can only pass through a node if it can leave towards a node other than
+            // the one it came from
+            let passable = incoming.iter().any(|&&(from, _)| {
+                search.neighbours[next]
+                    .iter()
+                    .any(|&(to, _)| to != from && usable(to))
+            });
+            if next == search.end || passable {
+                bound += incoming.into_iter().map(|&(_, d)| d).max().unwrap();
+                stack.push(next);
             }
         }
     }
 
-    (intersections, neighbours)
+    if reached.contains(search.end) {
+        Some(bound)
+    } else {
+        None
+    }
 }
 
-fn add_visited(visited: i64, node: usize) -> i64 {
-    return visited | (1 << node);
+fn longest_from(search: &mut Search, node: usize, dist: i32) {
+    if node == search.end {
+        if search.best.as_ref().is_none_or(|(best, _)| dist > *best) {
+            search.best = Some((dist, search.path.clone()));
+        }
+        return;
+    }
+    match remaining_bound(search, node) {
+        None => return,
+        Some(bound) if search.best.as_ref().is_some_and(|(best, _)| dist + bound <= *best) => return,
+        _ => {}
+    }
+
+    let neighbours = search.neighbours;
+    for &(next, dist_to_next) in &neighbours[node] {
+        if search.visited.contains(next) {
+            continue;
+        }
+        search.visited.insert(next);
+        search.path.push(next);
+        longest_from(search, next, dist + dist_to_next);
+        search.path.pop();
+        search.visited.remove(next);
+    }
 }
 
-fn contains(visited: i64, node: usize) -> bool {
-    return visited & (1 << node) != 0;
+fn reverse_edges(neighbours: &[Vec<(usize, i32)>]) -> Vec<Vec<(usize, i32)>> {
+    let mut incoming = vec![Vec::new(); neighbours.len()];
+    for (from, edges) in neighbours.iter().enumerate() {
+        for &(to, dist) in edges {
+            incoming[to].push((from, dist));
+        }
+    }
+    incoming
 }
 
-fn longest_path_len_2(maze: &Vec<Vec<char>>, start: Pos, end: Pos) -> i32 {
-    let (intersections, neighbours) = find_intersections(maze, start, end);
+fn longest_path_search(neighbours: &Vec<Vec<(usize, i32)>>) -> (i32, Vec<usize>) {
+    let mut search = Search {
+        neighbours,
+        incoming: reverse_edges(neighbours),
+        end: neighbours.len() - 1,
+        visited: BitSet::new(neighbours.len()),
+        path: vec![0],
+        best: None,
+    };
+    search.visited.insert(0);
+    longest_from(&mut search, 0, 0);
 
-    // Number of intersections should be < 64 so we can keep the set of visited nodes in a bitmask
-    assert!(intersections.len() < 64);
+    search.best.expect("no path from start to end")
+}
 
-    let start = 0;
-    let end = intersections.len() - 1;
+// Kahn's algorithm, None if the graph has a cycle
+fn topological_order(neighbours: &Vec<Vec<(usize, i32)>>) -> Option<Vec<usize>> {
+    let mut in_degree = vec![0; neighbours.len()];
+    for edges in neighbours {
+        for &(to, _) in edges {
+            in_degree[to] += 1;
+        }
+    }
 
-    let mut q = VecDeque::new();
-    q.push_back((start, add_visited(0, start), 0));
+    let mut ready = (0..neighbours.len())
+        .filter(|&node| in_degree[node] == 0)
+        .collect::<Vec<_>>();
+    let mut order = Vec::new();
+    while let Some(node) = ready.pop() {
+        order.push(node);
+        for &(to, _) in &neighbours[node] {
+            in_degree[to] -= 1;
+            if in_degree[to] == 0 {
+                ready.push(to);
+            }
+        }
+    }
 
-    let mut result = 0;
+    if order.len() == neighbours.len() {
+        Some(order)
+    } else {
+        None
+    }
+}
 
-    while !q.is_empty() {
-        let (node, visited, dist) = q.pop_front().unwrap();
+fn longest_path_dag(neighbours: &[Vec<(usize, i32)>], order: Vec<usize>) -> (i32, Vec<usize>) {
+    let (start, end) = (0, neighbours.len() - 1);
 
-        if node == end {
-            result = cmp::max(result, dist);
+    // Longest distance to each node, along with the node it was reached from
+    let mut longest: Vec<Option<(i32, usize)>> = vec![None; neighbours.len()];
+    longest[start] = Some((0, start));
+    for node in order {
+        let Some((dist, _)) = longest[node] else {
             continue;
-        }
-
+        };
         for &(next, dist_to_next) in &neighbours[node] {
-            if !contains(visited, next) {
-                q.push_back((next, add_visited(visited, next), dist + dist_to_next));
+            if longest[next].is_none_or(|(d, _)| dist + dist_to_next > d) {
+                longest[next] = Some((dist + dist_to_next, node));
             }
         }
     }
 
+    let (result, _) = longest[end].expect("no path from start to end");
+    let mut route = vec![end];
+    while *route.last().unwrap() != start {
+        let (_, prev) = longest[*route.last().unwrap()].unwrap();
+        route.push(prev);
+    }
+    route.reverse();
+
+    (result, route)
+}
+
+// Turns a route through intersections back into the cells walked, taking the longest corridor between each
+// consecutive pair like `find_intersections` does
+fn expand_route(maze: &Vec<Vec<char>>, intersections: &[Pos], route: &[usize], slippery: bool) -> Vec<Pos> {
+    let mut cells = vec![intersections[route[0]]];
+    for pair in route.windows(2) {
+        let (from, to) = (intersections[pair[0]], intersections[pair[1]]);
+        let corridor = empty_neighbours(from, maze)
+            .into_iter()
+            .filter_map(|first| follow_corridor(maze, from, first, slippery))
+            .filter(|path| *path.last().unwrap() == to)
+            .max_by_key(|path| path.len())
+            .unwrap();
+        cells.extend(corridor);
+    }
+    cells
+}
+
+// Returns the length, the cells along the route, and whether the slopes made the junction graph acyclic. If they
+// did, the longest path is a simple DP over the topological order, otherwise we have to fall back to searching
+fn longest_path(maze: &Vec<Vec<char>>, start: Pos, end: Pos) -> (i32, Vec<Pos>, bool) {
+    let (intersections, neighbours) = find_intersections(maze, start, end, true);
+
+    let order = topological_order(&neighbours);
+    let acyclic = order.is_some();
+    let (result, route) = match order {
+        Some(order) => longest_path_dag(&neighbours, order),
+        None => longest_path_search(&neighbours),
+    };
+
+    (result, expand_route(maze, &intersections, &route, true), acyclic)
+}
+
+fn longest_path_2(maze: &Vec<Vec<char>>, start: Pos, end: Pos) -> (i32, Vec<Pos>) {
+    let (intersections, neighbours) = find_intersections(maze, start, end, false);
+    let (result, route) = longest_path_search(&neighbours);
+    (result, expand_route(maze, &intersections, &route, false))
+}
+
+// The maze with the route marked like in the puzzle text - `S` for the start and `O` for every step taken
+fn render_route(maze: &[Vec<char>], route: &[Pos]) -> String {
+    let mut grid = maze.to_vec();
+    for (i, pos) in route.iter().enumerate() {
+        grid[pos.y as usize][pos.x as usize] = if i == 0 { 'S' } else { 'O' };
+    }
+
+    let mut result = String::new();
+    for row in grid {
+        result.extend(row);
+        result.push('\n');
+    }
+    result
+}
+
+fn route_svg(maze: &[Vec<char>], route: &[Pos]) -> String {
+    const CELL: i32 = 10;
+    let (n, m) = (maze.len() as i32, maze[0].len() as i32);
+    let on_route: HashSet<Pos> = route.iter().copied().collect();
+
+    let mut result = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        m * CELL,
+        n * CELL
+    );
+    for (y, row) in maze.iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            let pos = Pos { x: x as i32, y: y as i32 };
+            let colour = match c {
+                '#' => "#2e4d2e",
+                _ if on_route.contains(&pos) => "#f0d060",
+                '.' => "#e8e0d0",
+                _ => "#a0c8f0",
+            };
+            result += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{CELL}\" height=\"{CELL}\" fill=\"{colour}\"/>\n",
+                pos.x * CELL,
+                pos.y * CELL
+            );
+        }
+    }
+
+    let points = route
+        .iter()
+        .map(|pos| format!("{},{}", pos.x * CELL + CELL / 2, pos.y * CELL + CELL / 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    result += &format!("<polyline points=\"{points}\" fill=\"none\" stroke=\"#c03020\" stroke-width=\"2\"/>\n");
+    result += "</svg>\n";
     result
 }
 
-fn part_two() {
+fn part_two(output: &RouteOutput) {
     let maze = read_input();
     let start = Pos { x: 1, y: 0 };
     let end = Pos {
@@ -234,10 +455,26 @@ fn part_two() {
     assert!(maze[start.y as usize][start.x as usize] == '.');
     assert!(maze[end.y as usize][end.x as usize] == '.');
 
-    let result = longest_path_len_2(&maze, start, end);
+    let (result, route) = longest_path_2(&maze, start, end);
     println!("{result}");
+    show_route(&maze, &route, output);
 }
 
 fn main() {
-    part_two();
+    // `--slippery` solves part one instead. `--show` draws the route over the maze, `--svg <file>` writes it out
+    // as an image instead
+    let args = env::args().collect::<Vec<_>>();
+    let output = if let Some(i) = args.iter().position(|arg| arg == "--svg") {
+        RouteOutput::Svg(args.get(i + 1).expect("--svg needs a file name").clone())
+    } else if args.iter().any(|arg| arg == "--show") {
+        RouteOutput::Terminal
+    } else {
+        RouteOutput::None
+    };
+
+    if args.iter().any(|arg| arg == "--slippery") {
+        part_one(&output);
+    } else {
+        part_two(&output);
+    }
 }
@@ -0,0 +1,118 @@
+use std::fmt;
+
+// A grid of on/off cells, at most 128 wide and tall, stored both as one `u128` per row and one per column so that
+// whole lines can be compared or moved with a couple of bitwise operations. Bit `j` of row `i` is the cell (i, j),
+// which is also bit `i` of column `j`
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    rows: Vec<u128>,
+    cols: Vec<u128>,
+}
+
+// Mask with the lowest `n` bits set
+pub fn low_bits(n: usize) -> u128 {
+    if n >= 128 {
+        !0
+    } else {
+        (1 << n) - 1
+    }
+}
+
+fn transpose(lines: &[u128], len: usize) -> Vec<u128> {
+    let mut result = vec![0; len];
+    for (i, line) in lines.iter().enumerate() {
+        let mut bits = *line;
+        while bits != 0 {
+            let j = bits.trailing_zeros() as usize;
+            result[j] |= 1 << i;
+            bits &= bits - 1;
+        }
+    }
+    result
+}
+
+impl BitGrid {
+    pub fn new(height: usize, width: usize) -> BitGrid {
+        assert!(height <= 128 && width <= 128, "grid is larger than 128x128");
+        BitGrid {
+            rows: vec![0; height],
+            cols: vec![0; width],
+        }
+    }
+
+    // Cells equal to `on` are set
+    pub fn from_chars(lines: &[Vec<char>], on: char) -> BitGrid {
+        let width = lines.first().map_or(0, |line| line.len());
+        // Checked before shifting anything in, so a longer line can't overflow the shift
+        let longest = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        assert!(lines.len() <= 128 && longest <= 128, "grid is larger than 128x128");
+        let rows = lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c == on)
+                    .fold(0, |acc, (j, _)| acc | 1 << j)
+            })
+            .collect::<Vec<_>>();
+        BitGrid::from_rows(rows, width)
+    }
+
+    pub fn from_rows(rows: Vec<u128>, width: usize) -> BitGrid {
+        assert!(rows.len() <= 128 && width <= 128, "grid is larger than 128x128");
+        let cols = transpose(&rows, width);
+        BitGrid { rows, cols }
+    }
+
+    pub fn from_cols(cols: Vec<u128>, height: usize) -> BitGrid {
+        assert!(cols.len() <= 128 && height <= 128, "grid is larger than 128x128");
+        let rows = transpose(&cols, height);
+        BitGrid { rows, cols }
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+
+    pub fn rows(&self) -> &[u128] {
+        &self.rows
+    }
+
+    pub fn cols(&self) -> &[u128] {
+        &self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.rows[row] >> col & 1 == 1
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        if value {
+            self.rows[row] |= 1 << col;
+            self.cols[col] |= 1 << row;
+        } else {
+            self.rows[row] &= !(1 << col);
+            self.cols[col] &= !(1 << row);
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.rows.iter().map(|row| row.count_ones()).sum()
+    }
+}
+
+impl fmt::Debug for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.height() {
+            let line: String = (0..self.width())
+                .map(|col| if self.get(row, col) { '#' } else { '.' })
+                .collect();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use std::{cmp, collections::HashMap, hash::Hash};
+
+// All the detectors follow a sequence x0 = start, x1 = step(x0), ... and return (mu, lambda): x(mu) is the first
+// state that ever repeats and from there on x(i) == x(i + lambda)
+
+// Remembers every state it has seen, so it's the simplest one but needs the states to be hashable and keeps all of
+// them in memory
+pub fn find_cycle<T: Clone + Hash + Eq>(start: &T, step: impl Fn(&T) -> T) -> (usize, usize) {
+    let mut seen: HashMap<T, usize> = HashMap::new();
+    let mut current = start.clone();
+
+    for i in 0.. {
+        if let Some(&first) = seen.get(&current) {
+            return (first, i - first);
+        }
+        let next = step(&current);
+        seen.insert(current, i);
+        current = next;
+    }
+
+    unreachable!()
+}
+
+// Tortoise and hare - constant memory, about 3 * (mu + lambda) steps
+pub fn floyd<T: Clone + Eq>(start: &T, step: impl Fn(&T) -> T) -> (usize, usize) {
+    let mut tortoise = step(start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    let mut mu = 0;
+    tortoise = start.clone();
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    let mut lambda = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        lambda += 1;
+    }
+
+    (mu, lambda)
+}
+
+// Constant memory as well, but finds lambda first and needs fewer steps than Floyd
+pub fn brent<T: Clone + Eq>(start: &T, step: impl Fn(&T) -> T) -> (usize, usize) {
+    let (mut power, mut lambda) = (1, 1);
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare.clone();
+            power *= 2;
+            lambda = 0;
+        }
+        hare = step(&hare);
+        lambda += 1;
+    }
+
+    let mut mu = 0;
+    tortoise = start.clone();
+    hare = (0..lambda).fold(start.clone(), |current, _| step(&current));
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    (mu, lambda)
+}
+
+pub fn gcd(i1: i64, i2: i64) -> i64 {
+    let (mut d1, mut d2) = (cmp::max(i1, i2), cmp::min(i1, i2));
+
+    while d2 != 0 {
+        let rem = d1 % d2;
+        d1 = d2;
+        d2 = rem;
+    }
+
+    d1
+}
+
+pub fn lcm(i1: i64, i2: i64) -> i64 {
+    (i1 / gcd(i1, i2)) * i2
+}
+
+// (g, x, y) with a * x + b * y == g == gcd(a, b)
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+// The t with t = a (mod p) and t = b (mod q), as (t mod lcm(p, q), lcm(p, q)). The moduli don't need to be coprime,
+// in which case there might not be any such t
+pub fn crt(a: i64, p: i64, b: i64, q: i64) -> Option<(i64, i64)> {
+    let (a, p, b, q) = (a as i128, p as i128, b as i128, q as i128);
+    let (g, x, _) = extended_gcd(p, q);
+    if (b - a) % g != 0 {
+        return None;
+    }
+
+    let l = p / g * q;
+    let k = ((b - a) / g % (q / g) * x % (q / g)).rem_euclid(q / g);
+    Some(((a + p * k).rem_euclid(l) as i64, l as i64))
+}
+
+// The times at which something happens, for something that settles into a cycle: each time in `before` (all of them
+// less than `start`), and `hit + k * period` for every `hit` in `hits` (all in `start..start + period`) and k >= 0
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    pub start: i64,
+    pub period: i64,
+    pub before: Vec<i64>,
+    pub hits: Vec<i64>,
+}
+
+impl Schedule {
+    // Something that happens exactly at `first`, `first + period`, `first + 2 * period` etc.
+    pub fn periodic(first: i64, period: i64) -> Schedule {
+        Schedule {
+            start: first,
+            period,
+            before: Vec::new(),
+            hits: vec![first],
+        }
+    }
+
+    pub fn contains(&self, t: i64) -> bool {
+        if t < self.start {
+            self.before.contains(&t)
+        } else {
+            self.hits
+                .iter()
+                .any(|hit| (t - hit).rem_euclid(self.period) == 0)
+        }
+    }
+
+    pub fn first(&self) -> Option<i64> {
+        self.before.first().or(self.hits.first()).copied()
+    }
+
+    // The times that are in both schedules. The result's cycle starts once both have started theirs, and every pair
+    // of hits gives at most one hit in the combined cycle
+    pub fn combine(&self, other: &Schedule) -> Schedule {
+        let start = cmp::max(self.start, other.start);
+        let period = lcm(self.period, other.period);
+
+        let mut before = self
+            .before
+            .iter()
+            .copied()
+            .chain(self.hits.iter().flat_map(|&hit| (hit..start).step_by(self.period as usize)))
+            .filter(|&t| t < start && other.contains(t))
+            .collect::<Vec<_>>();
+        before.sort();
+
+        let mut hits = Vec::new();
+        for &a in &self.hits {
+            for &b in &other.hits {
+                if let Some((t, l)) = crt(a, self.period, b, other.period) {
+                    hits.push(start + (t - start).rem_euclid(l));
+                }
+            }
+        }
+        hits.sort();
+        hits.dedup();
+
+        Schedule {
+            start,
+            period,
+            before,
+            hits,
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod bitgrid;
+pub mod cycles;
+pub mod sequence;
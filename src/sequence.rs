@@ -0,0 +1,77 @@
+use std::fmt;
+
+use rug::Integer;
+
+// A polynomial sequence in Newton form: the value at index x is the sum over k of C(x, k) times the first element
+// of the k-th row of differences. Works for any integer x, negative ones included
+#[derive(Debug, Clone)]
+pub struct Polynomial {
+    coefficients: Vec<Integer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceError {
+    Empty,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SequenceError::Empty => write!(f, "the sequence is empty"),
+        }
+    }
+}
+
+fn is_constant(row: &[Integer]) -> bool {
+    row.iter().all(|x| *x == row[0])
+}
+
+// Fits the values, taken to be at indices 0, 1, 2, ... A row of differences with a single element counts as
+// constant, so n values always fit a polynomial of degree at most n - 1 (a single value is a constant one)
+pub fn fit(values: &[Integer]) -> Result<Polynomial, SequenceError> {
+    if values.is_empty() {
+        return Err(SequenceError::Empty);
+    }
+
+    let mut coefficients = Vec::new();
+    let mut current = values.to_vec();
+    loop {
+        coefficients.push(current[0].clone());
+        if is_constant(&current) {
+            return Ok(Polynomial { coefficients });
+        }
+
+        current = current
+            .windows(2)
+            .map(|pair| Integer::from(&pair[1] - &pair[0]))
+            .collect();
+    }
+}
+
+impl Polynomial {
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    pub fn at(&self, index: i64) -> Integer {
+        let mut result = Integer::new();
+        // C(index, k), built up one k at a time. Each step divides exactly, since every C(index, k) is an integer
+        let mut binomial = Integer::from(1);
+        for (k, coefficient) in self.coefficients.iter().enumerate() {
+            result += Integer::from(coefficient * &binomial);
+            binomial = binomial * (index - k as i64) / (k as i64 + 1);
+        }
+
+        result
+    }
+
+    // The `k` values after the first `len` ones
+    pub fn forward(&self, len: usize, k: usize) -> Vec<Integer> {
+        (0..k).map(|i| self.at((len + i) as i64)).collect()
+    }
+
+    // The `k` values before index 0, closest one first
+    pub fn backward(&self, k: usize) -> Vec<Integer> {
+        (1..=k).map(|i| self.at(-(i as i64))).collect()
+    }
+}